@@ -0,0 +1,198 @@
+pub mod common;
+
+use crate::common::admin_server::{
+    parse_admin_server_response, AdminCommand, Conf, Cons, Dump, Envi, IsReadOnly, Leader,
+    ObserverConnectionStatReset, Ruok, Srvr, Stat, VotingView, Wchc, Wchs,
+};
+use crate::common::four_letter_commands::ServerState;
+use crate::common::kube_api::FIXTURES_DIR;
+use anyhow::{anyhow, Result};
+use indoc::indoc;
+use std::fs;
+use std::path::Path;
+
+/// Directory (relative to FIXTURES_DIR) with responses in the format of a 3.5.8 ensemble. They
+/// are written by hand after the serialization of the 3.5.8 admin server, e.g. "avg_latency" is
+/// an integer and "uptime" is not reported before 3.6.
+const RESPONSES_DIR: &str = "admin_server_3_5_8";
+
+/// Parse the response of the admin server command `T` from RESPONSES_DIR.
+fn parse_fixture<T: AdminCommand>() -> Result<T> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join(FIXTURES_DIR)
+        .join(RESPONSES_DIR)
+        .join(format!("{}.json", T::NAME));
+    let response = fs::read_to_string(&path)
+        .map_err(|err| anyhow!("Could not read response [{}]: {}", path.display(), err))?;
+    T::from_response(&response)
+}
+
+#[test]
+fn test_parse_srvr() -> Result<()> {
+    let response = indoc!(
+        r#"
+        {
+          "version" : "3.5.8-f439ca583e70862c3068a1f2a7d4d068eec33315, built on 05/04/2020 15:07 GMT",
+          "read_only" : false,
+          "server_stats" : {
+            "packets_sent" : 10,
+            "packets_received" : 11,
+            "fsync_threshold_exceed_count" : 0,
+            "client_response_stats" : {
+              "last_buffer_size" : -1,
+              "min_buffer_size" : -1,
+              "max_buffer_size" : -1
+            },
+            "data_dir_size" : 457,
+            "log_dir_size" : 457,
+            "last_processed_zxid" : 4294967298,
+            "outstanding_requests" : 0,
+            "server_state" : "leader",
+            "avg_latency" : 0,
+            "max_latency" : 3,
+            "min_latency" : 0,
+            "num_alive_client_connections" : 0,
+            "provider_null" : false
+          },
+          "client_response" : {
+            "last_buffer_size" : -1,
+            "min_buffer_size" : -1,
+            "max_buffer_size" : -1
+          },
+          "proposal_stats" : {
+            "last_buffer_size" : 36,
+            "min_buffer_size" : 36,
+            "max_buffer_size" : 48
+          },
+          "node_count" : 5,
+          "command" : "server_stats",
+          "error" : null
+        }
+        "#
+    );
+
+    let srvr = Srvr::from_response(response)?;
+
+    assert_eq!(Some(ServerState::Leader), srvr.server_stats.server_state);
+    assert_eq!(3, srvr.server_stats.max_latency);
+    assert_eq!(5, srvr.node_count);
+    assert!(!srvr.read_only);
+    Ok(())
+}
+
+#[test]
+fn test_parse_envi_without_envelope() -> Result<()> {
+    let response = r#"{ "zookeeper.version" : "3.5.8", "host.name" : "node-1", "command" : "environment", "error" : null }"#;
+
+    let envi = Envi::from_response(response)?;
+
+    assert_eq!(2, envi.environment.len());
+    assert_eq!(
+        Some(&"node-1".to_string()),
+        envi.environment.get("host.name")
+    );
+    Ok(())
+}
+
+#[test]
+fn test_parse_leader_and_ruok() -> Result<()> {
+    let leader: Leader = parse_admin_server_response(
+        r#"{ "is_leader" : true, "leader_id" : 2, "leader_ip" : "10.0.0.2", "command" : "leader", "error" : null }"#,
+    )?;
+    assert!(leader.is_leader);
+    assert_eq!(Some(2), leader.leader_id);
+
+    Ruok::from_response(r#"{ "command" : "ruok", "error" : null }"#)?;
+    Ok(())
+}
+
+#[test]
+fn test_parse_admin_server_error() {
+    let response = r#"{ "command" : "leader", "error" : "This ZooKeeper instance is not currently serving requests" }"#;
+
+    assert!(Leader::from_response(response).is_err());
+}
+
+#[test]
+fn test_parse_fixture_stat_and_cons() -> Result<()> {
+    let stat: Stat = parse_fixture()?;
+    assert_eq!(
+        Some(ServerState::Follower),
+        stat.server.server_stats.server_state
+    );
+    assert_eq!(7, stat.server.node_count);
+    assert_eq!(1, stat.connections.len());
+    assert_eq!(Some(144115243396431873), stat.connections[0].session_id);
+    assert_eq!(
+        Some(&serde_json::json!("PING")),
+        stat.connections[0].details.get("last_operation")
+    );
+    assert!(stat.secure_connections.is_empty());
+
+    let cons: Cons = parse_fixture()?;
+    assert_eq!(2, cons.connections.len());
+    // connections without a session only report brief information
+    assert_eq!("10.0.0.5:40216", cons.connections[1].remote_socket_address);
+    assert_eq!(None, cons.connections[1].session_id);
+    assert_eq!(1, cons.connections[1].packets_received);
+    Ok(())
+}
+
+#[test]
+fn test_parse_fixture_conf() -> Result<()> {
+    let conf: Conf = parse_fixture()?;
+
+    assert_eq!(Some(2181), conf.client_port);
+    assert_eq!(Some("/tmp/zookeeper/version-2"), conf.data_dir.as_deref());
+    assert_eq!(Some(2000), conf.tick_time);
+    assert_eq!(Some(40000), conf.max_session_timeout);
+    assert_eq!(Some(2), conf.server_id);
+    assert!(conf.other.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_parse_fixture_sessions_and_watches() -> Result<()> {
+    let dump: Dump = parse_fixture()?;
+    assert_eq!(
+        Some(&vec![72057649358503937, 72057649358503938]),
+        dump.expiry_time_to_session_ids.get("1615800114000")
+    );
+    assert_eq!(
+        Some(&vec!["/integration-test-ephemeral".to_string()]),
+        dump.session_id_to_ephemeral_paths.get("144115243396431873")
+    );
+
+    let wchs: Wchs = parse_fixture()?;
+    assert_eq!(
+        (1, 2, 2),
+        (wchs.num_connections, wchs.num_paths, wchs.num_total_watches)
+    );
+
+    let wchc: Wchc = parse_fixture()?;
+    assert_eq!(
+        Some(2),
+        wchc.session_id_to_watched_paths
+            .get("144115243396431873")
+            .map(Vec::len)
+    );
+    Ok(())
+}
+
+#[test]
+fn test_parse_fixture_ensemble_commands() -> Result<()> {
+    let is_read_only: IsReadOnly = parse_fixture()?;
+    assert!(!is_read_only.read_only);
+
+    let voting_view: VotingView = parse_fixture()?;
+    assert_eq!(3, voting_view.current_config.len());
+    assert_eq!(
+        Some(&serde_json::json!(
+            "node-2:2888:3888:participant;0.0.0.0:2181"
+        )),
+        voting_view.current_config.get("2")
+    );
+
+    parse_fixture::<ObserverConnectionStatReset>()?;
+    Ok(())
+}
@@ -0,0 +1,209 @@
+pub mod common;
+
+use crate::common::admin_server::{
+    AdminClient, AdminServerOptions, AdminServerScheme, AdminServerTls, ClientIdentity, Ruok,
+};
+use crate::common::four_letter_commands::{send_4lw, ServerAccess, ARE_YOU_OK};
+use anyhow::Result;
+use openssl::asn1::Asn1Time;
+use openssl::bn::BigNum;
+use openssl::ec::{EcGroup, EcKey};
+use openssl::hash::MessageDigest;
+use openssl::nid::Nid;
+use openssl::pkey::{PKey, Private};
+use openssl::ssl::{SslAcceptor, SslMethod, SslVerifyMode};
+use openssl::x509::extension::{BasicConstraints, SubjectAlternativeName};
+use openssl::x509::{X509NameBuilder, X509};
+use stackable_zookeeper_crd::ZookeeperVersion;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::thread;
+
+#[test]
+fn test_https_with_ca_bundle() -> Result<()> {
+    let ca = CertificateAuthority::new()?;
+    let (server_cert, server_key) = ca.sign("localhost")?;
+    let port = start_tls_admin_server(&ca, server_cert, server_key, false)?;
+
+    let options = https_options(port, &ca, None)?;
+    AdminClient::with_options("localhost", &options)?.command::<Ruok>()?;
+
+    Ok(())
+}
+
+#[test]
+fn test_https_rejects_unknown_ca() -> Result<()> {
+    let ca = CertificateAuthority::new()?;
+    let (server_cert, server_key) = ca.sign("localhost")?;
+    let port = start_tls_admin_server(&ca, server_cert, server_key, false)?;
+
+    let options = https_options(port, &CertificateAuthority::new()?, None)?;
+    assert!(AdminClient::with_options("localhost", &options)?
+        .command::<Ruok>()
+        .is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_https_with_client_certificate() -> Result<()> {
+    let ca = CertificateAuthority::new()?;
+    let (server_cert, server_key) = ca.sign("localhost")?;
+    let port = start_tls_admin_server(&ca, server_cert, server_key, true)?;
+
+    let (client_cert, client_key) = ca.sign("admin-client")?;
+    let identity = ClientIdentity {
+        certificate: client_cert.to_pem()?,
+        private_key: client_key.private_key_to_pem_pkcs8()?,
+    };
+
+    let options = https_options(port, &ca, Some(identity))?;
+    AdminClient::with_options("localhost", &options)?.command::<Ruok>()?;
+
+    let options = https_options(port, &ca, None)?;
+    assert!(AdminClient::with_options("localhost", &options)?
+        .command::<Ruok>()
+        .is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_checks_use_admin_server_options() -> Result<()> {
+    let ca = CertificateAuthority::new()?;
+    let (server_cert, server_key) = ca.sign("localhost")?;
+    let port = start_tls_admin_server(&ca, server_cert, server_key, false)?;
+
+    let access =
+        ServerAccess::new(&ZookeeperVersion::v3_5_8)?.admin_server(https_options(port, &ca, None)?);
+    assert_eq!(ARE_YOU_OK, send_4lw(&access, ARE_YOU_OK, "localhost")?);
+    Ok(())
+}
+
+fn https_options(
+    port: u16,
+    ca: &CertificateAuthority,
+    client_identity: Option<ClientIdentity>,
+) -> Result<AdminServerOptions> {
+    Ok(AdminServerOptions {
+        scheme: AdminServerScheme::Https,
+        port,
+        tls: AdminServerTls {
+            ca_bundle: Some(ca.certificate.to_pem()?),
+            client_identity,
+        },
+    })
+}
+
+/// Self-signed CA generated at test time which signs server and client certificates.
+struct CertificateAuthority {
+    certificate: X509,
+    key: PKey<Private>,
+}
+
+impl CertificateAuthority {
+    fn new() -> Result<Self> {
+        let key = generate_key()?;
+        let mut builder = certificate_builder("test-ca", &key)?;
+        builder.set_issuer_name(subject_name("test-ca")?.as_ref())?;
+        builder.append_extension(BasicConstraints::new().critical().ca().build()?)?;
+        builder.sign(&key, MessageDigest::sha256())?;
+
+        Ok(CertificateAuthority {
+            certificate: builder.build(),
+            key,
+        })
+    }
+
+    fn sign(&self, common_name: &str) -> Result<(X509, PKey<Private>)> {
+        let key = generate_key()?;
+        let mut builder = certificate_builder(common_name, &key)?;
+        builder.set_issuer_name(self.certificate.subject_name())?;
+        let subject_alternative_name = SubjectAlternativeName::new()
+            .dns(common_name)
+            .ip("127.0.0.1")
+            .build(&builder.x509v3_context(Some(&self.certificate), None))?;
+        builder.append_extension(subject_alternative_name)?;
+        builder.sign(&self.key, MessageDigest::sha256())?;
+
+        Ok((builder.build(), key))
+    }
+}
+
+fn generate_key() -> Result<PKey<Private>> {
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?;
+    Ok(PKey::from_ec_key(EcKey::generate(&group)?)?)
+}
+
+fn subject_name(common_name: &str) -> Result<openssl::x509::X509Name> {
+    let mut name = X509NameBuilder::new()?;
+    name.append_entry_by_text("CN", common_name)?;
+    Ok(name.build())
+}
+
+fn certificate_builder(
+    common_name: &str,
+    key: &PKey<Private>,
+) -> Result<openssl::x509::X509Builder> {
+    let mut builder = X509::builder()?;
+    builder.set_version(2)?;
+    builder.set_serial_number(BigNum::from_u32(rand_serial())?.to_asn1_integer()?.as_ref())?;
+    builder.set_subject_name(subject_name(common_name)?.as_ref())?;
+    builder.set_pubkey(key)?;
+    builder.set_not_before(Asn1Time::days_from_now(0)?.as_ref())?;
+    builder.set_not_after(Asn1Time::days_from_now(1)?.as_ref())?;
+    Ok(builder)
+}
+
+fn rand_serial() -> u32 {
+    uuid::Uuid::new_v4().as_fields().0
+}
+
+/// Start a TLS stand-in for the admin server on a random local port that answers every
+/// request with a successful "ruok" response. Returns the port.
+fn start_tls_admin_server(
+    ca: &CertificateAuthority,
+    certificate: X509,
+    key: PKey<Private>,
+    require_client_certificate: bool,
+) -> Result<u16> {
+    let mut acceptor = SslAcceptor::mozilla_intermediate(SslMethod::tls())?;
+    acceptor.set_certificate(&certificate)?;
+    acceptor.set_private_key(&key)?;
+    acceptor.check_private_key()?;
+    if require_client_certificate {
+        acceptor.cert_store_mut().add_cert(ca.certificate.clone())?;
+        acceptor.set_verify(SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT);
+    }
+    let acceptor = acceptor.build();
+
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let port = listener.local_addr()?.port();
+
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            // failed handshakes (e.g. missing client certificates) are expected in some tests
+            let mut stream = match acceptor.accept(stream) {
+                Ok(stream) => stream,
+                Err(_) => continue,
+            };
+
+            let mut reader = BufReader::new(&mut stream);
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap_or(0) > 2 {
+                line.clear();
+            }
+
+            let body = r#"{ "command" : "ruok", "error" : null }"#;
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            let _ = stream.shutdown();
+        }
+    });
+
+    Ok(port)
+}
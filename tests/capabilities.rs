@@ -0,0 +1,91 @@
+pub mod common;
+
+use crate::common::capabilities::{MetricsFormat, Protocol, ZkCapabilities};
+use crate::common::four_letter_commands::{ServerAccess, ARE_YOU_OK, I_AM_OK};
+use crate::common::zookeeper::all_versions;
+use anyhow::Result;
+use semver::Version;
+use stackable_zookeeper_crd::ZookeeperVersion;
+
+#[test]
+fn test_capabilities_3_4_14() -> Result<()> {
+    let capabilities = ZkCapabilities::for_version(&ZookeeperVersion::v3_4_14)?;
+
+    assert_eq!(
+        ZkCapabilities {
+            protocol: Protocol::FourLetterWord,
+            ruok_reply: I_AM_OK,
+            admin_port: None,
+            dynamic_reconfig: false,
+            four_letter_word_whitelist: false,
+            metrics_format: MetricsFormat::JmxExporter,
+        },
+        capabilities
+    );
+    Ok(())
+}
+
+#[test]
+fn test_capabilities_3_5_8() -> Result<()> {
+    let capabilities = ZkCapabilities::for_version(&ZookeeperVersion::v3_5_8)?;
+
+    assert_eq!(
+        ZkCapabilities {
+            protocol: Protocol::AdminServer,
+            ruok_reply: ARE_YOU_OK,
+            admin_port: Some(8080),
+            dynamic_reconfig: true,
+            four_letter_word_whitelist: true,
+            metrics_format: MetricsFormat::JmxExporter,
+        },
+        capabilities
+    );
+    Ok(())
+}
+
+#[test]
+fn test_capabilities_version_boundaries() {
+    let capabilities =
+        |version: &str| ZkCapabilities::for_semver(&Version::parse(version).unwrap());
+
+    assert!(!capabilities("3.4.99").dynamic_reconfig);
+    assert!(capabilities("3.5.0").dynamic_reconfig);
+
+    assert_eq!(Protocol::FourLetterWord, capabilities("3.5.2").protocol);
+    assert!(!capabilities("3.5.2").four_letter_word_whitelist);
+    assert_eq!(Protocol::AdminServer, capabilities("3.5.3").protocol);
+    assert!(capabilities("3.5.3").four_letter_word_whitelist);
+
+    assert_eq!(
+        MetricsFormat::JmxExporter,
+        capabilities("3.5.9").metrics_format
+    );
+    assert_eq!(
+        MetricsFormat::Prometheus,
+        capabilities("3.6.0").metrics_format
+    );
+}
+
+#[test]
+fn test_capabilities_of_all_versions() -> Result<()> {
+    for version in all_versions() {
+        let capabilities = ZkCapabilities::for_version(&version)?;
+
+        // the admin server mirrors the command, the raw four letter word answers with "imok"
+        match capabilities.protocol {
+            Protocol::AdminServer => {
+                assert_eq!(ARE_YOU_OK, capabilities.ruok_reply);
+                // the checks request the admin server on its default port
+                assert_eq!(
+                    capabilities.admin_port,
+                    Some(ServerAccess::new(&version)?.admin_server.port)
+                );
+            }
+            Protocol::FourLetterWord => {
+                assert_eq!(I_AM_OK, capabilities.ruok_reply);
+                assert_eq!(None, capabilities.admin_port);
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,200 @@
+pub mod common;
+
+use crate::common::checks::{
+    check_server_hosts, check_server_id, find_leader, verify_ensemble_roles, verify_pod_replaced,
+    wait_for_replication,
+};
+use crate::common::four_letter_commands::ServerState;
+use crate::common::mock_server::mock_pod;
+use crate::common::zoo_cfg::ZooCfg;
+use anyhow::{anyhow, Result};
+use integration_test_commons::test::prelude::Pod;
+use std::time::Duration;
+
+fn states(states: &[ServerState]) -> Vec<(String, ServerState)> {
+    states
+        .iter()
+        .enumerate()
+        .map(|(index, state)| (format!("pod-{}", index), *state))
+        .collect()
+}
+
+#[test]
+fn test_verify_ensemble_roles_single_server() {
+    assert!(verify_ensemble_roles(&states(&[ServerState::Standalone])).is_ok());
+    assert!(verify_ensemble_roles(&states(&[ServerState::Leader])).is_err());
+}
+
+#[test]
+fn test_verify_ensemble_roles_ensemble() {
+    assert!(verify_ensemble_roles(&states(&[
+        ServerState::Follower,
+        ServerState::Leader,
+        ServerState::Follower
+    ]))
+    .is_ok());
+}
+
+#[test]
+fn test_find_leader() -> Result<()> {
+    assert_eq!(
+        "pod-1",
+        find_leader(&states(&[ServerState::Follower, ServerState::Leader]))?
+    );
+    // the remaining servers have not elected a new leader yet
+    assert!(find_leader(&states(&[ServerState::Follower, ServerState::Follower])).is_err());
+    assert!(find_leader(&states(&[ServerState::Standalone])).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_verify_ensemble_roles_detects_invalid_ensembles() {
+    // every server came up standalone
+    assert!(verify_ensemble_roles(&states(&[
+        ServerState::Standalone,
+        ServerState::Standalone,
+        ServerState::Standalone
+    ]))
+    .is_err());
+    // split brain
+    assert!(verify_ensemble_roles(&states(&[
+        ServerState::Leader,
+        ServerState::Leader,
+        ServerState::Follower
+    ]))
+    .is_err());
+    // no leader elected
+    assert!(verify_ensemble_roles(&states(&[
+        ServerState::Follower,
+        ServerState::Follower,
+        ServerState::Follower
+    ]))
+    .is_err());
+    assert!(verify_ensemble_roles(&states(&[
+        ServerState::Leader,
+        ServerState::Standalone,
+        ServerState::Follower
+    ]))
+    .is_err());
+}
+
+#[test]
+fn test_server_entries_match_pods() -> Result<()> {
+    let servers =
+        ZooCfg::parse("server.1=node-1:2888:3888\nserver.2=node-2:2888:3888")?.servers()?;
+    let pods = vec![
+        mock_pod("zk-1", "node-1", None, None),
+        mock_pod("zk-2", "node-2", None, None),
+    ];
+
+    check_server_hosts(&servers, &pods)?;
+    check_server_id(&servers, &pods[0], 1)?;
+    check_server_id(&servers, &pods[1], 2)?;
+
+    // ids swapped after scaling
+    assert!(check_server_id(&servers, &pods[0], 2).is_err());
+    // no server.3 property
+    assert!(check_server_id(&servers, &pods[0], 3).is_err());
+    // server.2 points to a node without ZooKeeper pod
+    assert!(check_server_hosts(&servers, &pods[..1]).is_err());
+
+    // node-1 listed twice while node-2 is missing
+    let servers =
+        ZooCfg::parse("server.1=node-1:2888:3888\nserver.2=node-1:2888:3888")?.servers()?;
+    assert!(check_server_hosts(&servers, &pods).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_verify_pod_replaced() {
+    let deleted = mock_pod("zk-1", "node-1", Some("uid-1"), Some(true));
+    let others = vec![
+        mock_pod("zk-2", "node-1", Some("uid-2"), Some(true)),
+        mock_pod("zk-3", "node-1", Some("uid-3"), Some(true)),
+    ];
+    let with = |pod: Pod| {
+        let mut pods = others.clone();
+        pods.push(pod);
+        pods
+    };
+
+    assert!(verify_pod_replaced(
+        &deleted,
+        &with(mock_pod("zk-1", "node-1", Some("uid-4"), Some(true))),
+        3
+    )
+    .is_ok());
+    // not deleted yet
+    assert!(verify_pod_replaced(&deleted, &with(deleted.clone()), 3).is_err());
+    // not recreated yet
+    assert!(verify_pod_replaced(&deleted, &others, 3).is_err());
+    // recreated but not ready
+    assert!(verify_pod_replaced(
+        &deleted,
+        &with(mock_pod("zk-1", "node-1", Some("uid-4"), Some(false))),
+        3
+    )
+    .is_err());
+}
+
+/// Reader which fails a number of polls and afterwards sees the znode after a number of polls.
+struct MockReader {
+    failures: usize,
+    polls_until_replicated: usize,
+    polls: usize,
+}
+
+impl MockReader {
+    fn new(failures: usize, polls_until_replicated: usize) -> Self {
+        MockReader {
+            failures,
+            polls_until_replicated,
+            polls: 0,
+        }
+    }
+
+    fn poll(&mut self) -> Result<bool> {
+        self.polls += 1;
+        if self.failures > 0 {
+            self.failures -= 1;
+            return Err(anyhow!("connection lost"));
+        }
+        Ok(self.polls > self.polls_until_replicated)
+    }
+}
+
+#[test]
+fn test_wait_for_replication_keeps_failed_readers_pending() -> Result<()> {
+    let mut readers = vec![
+        ("zk-2".to_string(), MockReader::new(0, 2)),
+        ("zk-3".to_string(), MockReader::new(1, 0)),
+    ];
+
+    let mut latencies = wait_for_replication(
+        "test znode",
+        &mut readers,
+        Duration::from_secs(5),
+        MockReader::poll,
+    )?;
+    latencies.sort();
+
+    let names: Vec<&str> = latencies.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(vec!["zk-2", "zk-3"], names);
+    // the failed poll is repeated until the reader sees the znode
+    assert_eq!(2, readers[1].1.polls);
+    assert_eq!(3, readers[0].1.polls);
+    Ok(())
+}
+
+#[test]
+fn test_wait_for_replication_times_out() {
+    let mut readers = vec![("zk-2".to_string(), MockReader::new(0, usize::MAX))];
+
+    let result = wait_for_replication(
+        "test znode",
+        &mut readers,
+        Duration::from_millis(300),
+        MockReader::poll,
+    );
+    assert!(result.is_err());
+}
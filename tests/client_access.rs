@@ -0,0 +1,86 @@
+pub mod common;
+
+use crate::common::capabilities::ZkCapabilities;
+use crate::common::checks::{check_connect_string, check_services};
+use crate::common::diagnostics::with_diagnostics;
+use crate::common::discovery::{connect_string_for_pods, ConnectString};
+use crate::common::four_letter_commands::DEFAULT_CLIENT_PORT;
+use crate::common::zookeeper::{append_random_characters, build_test_cluster, ZkClusterBuilder};
+use anyhow::Result;
+use stackable_zookeeper_crd::ZookeeperVersion;
+
+/// Number of servers of the ensemble, so the services and the connect string have to cover
+/// several pods.
+const REPLICAS: usize = 3;
+
+const METRICS_PORT: u16 = 9505;
+
+version_tests!(
+    #[ignore = "unconfirmed whether the operator creates services, no issue filed yet"]
+    test_services
+);
+version_tests!(test_connect_string);
+
+/// Checks the services of the cluster. Ignored until it is confirmed which services the
+/// operator creates, run it with `--ignored` to check an operator version.
+fn test_services(version: &ZookeeperVersion) -> Result<()> {
+    let name = append_random_characters("simple");
+    let capabilities = ZkCapabilities::for_version(version)?;
+    let mut cluster = build_test_cluster();
+
+    with_diagnostics(
+        &format!("test_services_{}", version),
+        &mut cluster,
+        &name,
+        |cluster| {
+            let (zookeeper_cr, expected_pod_count) = ZkClusterBuilder::new(&name, version)
+                .replicas(REPLICAS)
+                .metrics_port(METRICS_PORT)
+                .build()?;
+            cluster.create_or_update(&zookeeper_cr, expected_pod_count)?;
+
+            let mut container_ports =
+                vec![("client", DEFAULT_CLIENT_PORT), ("metrics", METRICS_PORT)];
+            if let Some(admin_port) = capabilities.admin_port {
+                container_ports.push(("admin", admin_port));
+            }
+
+            check_services(
+                &cluster.client,
+                &name,
+                cluster.list_pods().as_slice(),
+                container_ports.as_slice(),
+                "zookeeper",
+            )
+        },
+    )
+}
+
+/// Checks that a client given the connect string of the cluster reaches every server of it.
+fn test_connect_string(version: &ZookeeperVersion) -> Result<()> {
+    let name = append_random_characters("simple");
+    let mut cluster = build_test_cluster();
+
+    with_diagnostics(
+        &format!("test_connect_string_{}", version),
+        &mut cluster,
+        &name,
+        |cluster| {
+            let (zookeeper_cr, expected_pod_count) = ZkClusterBuilder::new(&name, version)
+                .replicas(REPLICAS)
+                .build()?;
+            cluster.create_or_update(&zookeeper_cr, expected_pod_count)?;
+
+            let pods = cluster.list_pods();
+            let connect_string = connect_string_for_pods(
+                pods.as_slice(),
+                DEFAULT_CLIENT_PORT,
+                Some(&format!("/{}", name)),
+            )?;
+            // clients receive the connect string as text
+            let connect_string = ConnectString::parse(&connect_string.to_string())?;
+
+            check_connect_string(&connect_string, pods.as_slice(), DEFAULT_CLIENT_PORT)
+        },
+    )
+}
@@ -0,0 +1,385 @@
+use crate::common::diagnostics::record_response;
+use crate::common::four_letter_commands::{Mntr, ServerState};
+use anyhow::{anyhow, Result};
+use openssl::pkcs12::Pkcs12;
+use openssl::pkey::PKey;
+use openssl::x509::X509;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+
+/// Default port of the ZooKeeper admin server.
+pub const DEFAULT_ADMIN_PORT: u16 = 8080;
+
+/// Timeout for a single admin server request.
+const ADMIN_SERVER_REQUEST_TIMEOUT: u64 = 10;
+
+/// Protocol the admin server is exposed with.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AdminServerScheme {
+    Http,
+    Https,
+}
+
+impl fmt::Display for AdminServerScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AdminServerScheme::Http => write!(f, "http"),
+            AdminServerScheme::Https => write!(f, "https"),
+        }
+    }
+}
+
+/// TLS settings for talking to an admin server via https. All certificates and keys are PEM
+/// encoded.
+#[derive(Clone, Debug, Default)]
+pub struct AdminServerTls {
+    /// CA bundle used to verify the server certificate (may contain several certificates).
+    /// If not set, the system trust store is used.
+    pub ca_bundle: Option<Vec<u8>>,
+    /// Client certificate and key presented to the server if it requires client authentication.
+    pub client_identity: Option<ClientIdentity>,
+}
+
+/// A PEM encoded client certificate and its private key.
+#[derive(Clone, Debug)]
+pub struct ClientIdentity {
+    pub certificate: Vec<u8>,
+    pub private_key: Vec<u8>,
+}
+
+/// Describes how the admin server of a cluster is reached.
+#[derive(Clone, Debug)]
+pub struct AdminServerOptions {
+    pub scheme: AdminServerScheme,
+    pub port: u16,
+    /// Only used if the scheme is https.
+    pub tls: AdminServerTls,
+}
+
+impl Default for AdminServerOptions {
+    fn default() -> Self {
+        AdminServerOptions {
+            scheme: AdminServerScheme::Http,
+            port: DEFAULT_ADMIN_PORT,
+            tls: AdminServerTls::default(),
+        }
+    }
+}
+
+/// Client for the ZooKeeper admin server (available from version 3.5.3 onwards).
+/// Commands are requested via "SCHEME://HOST:PORT/commands/COMMAND" and answered with a JSON
+/// object that contains the command name, an optional error and the command specific fields.
+pub struct AdminClient {
+    base_url: String,
+    client: reqwest::blocking::Client,
+}
+
+impl AdminClient {
+    /// Create a plain http client for the admin server running on host_name with the default
+    /// admin port.
+    pub fn new(host_name: &str) -> Result<Self> {
+        Self::with_options(host_name, &AdminServerOptions::default())
+    }
+
+    /// Create a client for the admin server running on host_name with the scheme, port and
+    /// TLS settings of the provided options.
+    pub fn with_options(host_name: &str, options: &AdminServerOptions) -> Result<Self> {
+        let mut builder = reqwest::blocking::Client::builder()
+            .timeout(Duration::from_secs(ADMIN_SERVER_REQUEST_TIMEOUT));
+
+        if options.scheme == AdminServerScheme::Https {
+            if let Some(ca_bundle) = &options.tls.ca_bundle {
+                // reqwest only reads the first certificate of a PEM file, so we split the bundle
+                for certificate in X509::stack_from_pem(ca_bundle)? {
+                    builder = builder.add_root_certificate(reqwest::Certificate::from_der(
+                        &certificate.to_der()?,
+                    )?);
+                }
+            }
+
+            if let Some(identity) = &options.tls.client_identity {
+                builder = builder.identity(identity.to_reqwest_identity()?);
+            }
+        }
+
+        Ok(AdminClient {
+            base_url: format!("{}://{}:{}", options.scheme, host_name, options.port),
+            client: builder.build()?,
+        })
+    }
+
+    /// Request the command `T` and parse the typed response, e.g.
+    /// `AdminClient::new("node-1")?.command::<Srvr>()`.
+    pub fn command<T: AdminCommand>(&self) -> Result<T> {
+        T::from_response(&self.request(T::NAME)?)
+    }
+
+    /// Send a request for the given command and return the raw JSON body.
+    pub fn request(&self, command: &str) -> Result<String> {
+        let url = format!("{}/commands/{}", self.base_url, command);
+
+        println!("Requesting [{}]", url);
+        let body = self
+            .client
+            .get(&url)
+            .send()
+            .and_then(|response| response.text())
+            .map_err(anyhow::Error::from);
+        record_response(&url, &body);
+        let body = body?;
+
+        println!("Received: {}", body);
+        Ok(body)
+    }
+}
+
+impl ClientIdentity {
+    /// The native TLS backend of reqwest only accepts PKCS#12 identities, so certificate and
+    /// key are bundled into a PKCS#12 archive with an empty password.
+    fn to_reqwest_identity(&self) -> Result<reqwest::Identity> {
+        let certificate = X509::from_pem(&self.certificate)?;
+        let private_key = PKey::private_key_from_pem(&self.private_key)?;
+        let pkcs12 = Pkcs12::builder().build("", "admin-client", &private_key, &certificate)?;
+
+        Ok(reqwest::Identity::from_pkcs12_der(&pkcs12.to_der()?, "")?)
+    }
+}
+
+/// A command that can be requested from the admin server with a typed response.
+pub trait AdminCommand: Sized {
+    /// The command name used in the request url.
+    const NAME: &'static str;
+
+    /// Parse the JSON body returned by the admin server.
+    fn from_response(response: &str) -> Result<Self>;
+}
+
+/// Every admin server response contains the executed command and an error which is "null"
+/// (which in serde parses to None) if no error occurred.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct AdminServerResponse {
+    pub command: String,
+    pub error: Option<String>,
+}
+
+/// Parse the JSON body of an admin server response into `T`. Returns an error if the admin
+/// server reported one. The "command" and "error" fields are removed before parsing `T`.
+pub fn parse_admin_server_response<T: DeserializeOwned>(response: &str) -> Result<T> {
+    let mut json: serde_json::Value = serde_json::from_str(response)?;
+    let envelope: AdminServerResponse = serde_json::from_value(json.clone())?;
+
+    if let Some(error) = envelope.error {
+        return Err(anyhow!(
+            "Received error while executing command [{}] to admin server: {}",
+            envelope.command,
+            error
+        ));
+    }
+
+    if let Some(object) = json.as_object_mut() {
+        object.remove("command");
+        object.remove("error");
+    }
+
+    Ok(serde_json::from_value(json)?)
+}
+
+macro_rules! admin_command {
+    ($command:ty, $name:expr) => {
+        impl AdminCommand for $command {
+            const NAME: &'static str = $name;
+
+            fn from_response(response: &str) -> Result<Self> {
+                parse_admin_server_response(response)
+            }
+        }
+    };
+}
+
+impl AdminCommand for Mntr {
+    const NAME: &'static str = "monitor";
+
+    fn from_response(response: &str) -> Result<Self> {
+        Mntr::from_admin_server_response(response)
+    }
+}
+
+/// Tests if the server is running in a non-error state.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Ruok {}
+admin_command!(Ruok, "ruok");
+
+/// Statistics collected by the server (part of "srvr" and "stat").
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct ServerStats {
+    pub packets_sent: u64,
+    pub packets_received: u64,
+    pub max_latency: i64,
+    pub min_latency: i64,
+    pub avg_latency: f64,
+    pub outstanding_requests: u64,
+    pub server_state: Option<ServerState>,
+    pub num_alive_client_connections: u64,
+    pub last_processed_zxid: i64,
+    pub data_dir_size: u64,
+    pub log_dir_size: u64,
+    pub fsync_threshold_exceed_count: u64,
+}
+
+/// Response buffer sizes of the server.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct ClientResponseStats {
+    pub last_buffer_size: i64,
+    pub min_buffer_size: i64,
+    pub max_buffer_size: i64,
+}
+
+/// Server information like version, state and node count.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Srvr {
+    pub version: String,
+    pub read_only: bool,
+    pub server_stats: ServerStats,
+    #[serde(default)]
+    pub client_response: ClientResponseStats,
+    pub node_count: u64,
+}
+admin_command!(Srvr, "srvr");
+
+/// Same as "srvr" including the connections.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Stat {
+    #[serde(flatten)]
+    pub server: Srvr,
+    #[serde(default)]
+    pub connections: Vec<Connection>,
+    #[serde(default)]
+    pub secure_connections: Vec<Connection>,
+}
+admin_command!(Stat, "stat");
+
+/// Information about a single client connection.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Connection {
+    pub remote_socket_address: String,
+    /// Only reported for detailed connection information (e.g. "stat" and "cons").
+    #[serde(default)]
+    pub session_id: Option<i64>,
+    #[serde(default)]
+    pub outstanding_requests: u64,
+    #[serde(default)]
+    pub packets_received: u64,
+    #[serde(default)]
+    pub packets_sent: u64,
+    /// Additional details like latencies or the last operation.
+    #[serde(flatten)]
+    pub details: BTreeMap<String, serde_json::Value>,
+}
+
+/// Lists the connections of the server.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Cons {
+    #[serde(default)]
+    pub connections: Vec<Connection>,
+    #[serde(default)]
+    pub secure_connections: Vec<Connection>,
+}
+admin_command!(Cons, "connections");
+
+/// The serving configuration of the server.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct Conf {
+    pub client_port: Option<u16>,
+    pub secure_client_port: Option<u16>,
+    pub data_dir: Option<String>,
+    pub data_log_dir: Option<String>,
+    pub tick_time: Option<u32>,
+    pub max_client_cnxns: Option<u32>,
+    pub min_session_timeout: Option<i64>,
+    pub max_session_timeout: Option<i64>,
+    pub server_id: Option<u64>,
+    pub init_limit: Option<u32>,
+    pub sync_limit: Option<u32>,
+    pub election_alg: Option<u32>,
+    pub election_port: Option<u16>,
+    pub quorum_port: Option<u16>,
+    pub peer_type: Option<u32>,
+    /// Configuration values not mapped above.
+    #[serde(flatten)]
+    pub other: BTreeMap<String, serde_json::Value>,
+}
+admin_command!(Conf, "configuration");
+
+/// Lists the outstanding sessions and ephemeral nodes. This only works on the leader.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct Dump {
+    pub expiry_time_to_session_ids: BTreeMap<String, Vec<i64>>,
+    pub session_id_to_ephemeral_paths: BTreeMap<String, Vec<String>>,
+}
+admin_command!(Dump, "dump");
+
+/// Details about the serving environment (e.g. "zookeeper.version" or "java.version").
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Envi {
+    #[serde(flatten)]
+    pub environment: BTreeMap<String, String>,
+}
+admin_command!(Envi, "environment");
+
+/// Brief information on watches for the server.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Wchs {
+    pub num_connections: u64,
+    pub num_paths: u64,
+    pub num_total_watches: u64,
+}
+admin_command!(Wchs, "watch_summary");
+
+/// Detailed information on watches for the server, by session.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct Wchc {
+    pub session_id_to_watched_paths: BTreeMap<String, Vec<String>>,
+}
+admin_command!(Wchc, "watches");
+
+/// Tests if the server is running in read-only mode.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct IsReadOnly {
+    pub read_only: bool,
+}
+admin_command!(IsReadOnly, "is_read_only");
+
+/// Tests if the server is the leader of the ensemble.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Leader {
+    pub is_leader: bool,
+    #[serde(default)]
+    pub leader_id: Option<u64>,
+    #[serde(default)]
+    pub leader_ip: Option<String>,
+}
+admin_command!(Leader, "leader");
+
+/// The current voting members of the ensemble by server id.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct VotingView {
+    pub current_config: BTreeMap<String, serde_json::Value>,
+}
+admin_command!(VotingView, "voting_view");
+
+/// Resets all observer connection statistics.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ObserverConnectionStatReset {}
+admin_command!(
+    ObserverConnectionStatReset,
+    "observer_connection_stat_reset"
+);
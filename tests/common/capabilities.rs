@@ -0,0 +1,77 @@
+use crate::common::admin_server::DEFAULT_ADMIN_PORT;
+use crate::common::four_letter_commands::{ARE_YOU_OK, I_AM_OK};
+use anyhow::Result;
+use semver::Version;
+use stackable_zookeeper_crd::ZookeeperVersion;
+
+/// How the checks request commands like "ruok" or "mntr" from a server.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Protocol {
+    /// Raw four letter words on the client port.
+    FourLetterWord,
+    /// Http requests to the admin server ("/commands/<command>").
+    AdminServer,
+}
+
+/// The format of the metrics exposed on the metrics port.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MetricsFormat {
+    /// The Prometheus JMX exporter agent translates the ZooKeeper MBeans (e.g.
+    /// "zookeeper_NumAliveConnections", labelled with "memberType" and "replicaId" on ensembles).
+    JmxExporter,
+    /// The native Prometheus metrics provider of ZooKeeper (e.g. "znode_count").
+    Prometheus,
+}
+
+/// What a ZooKeeper version supports and how it has to be checked. All version dependent
+/// decisions of the checks are made here instead of comparing versions inline.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ZkCapabilities {
+    pub protocol: Protocol,
+    /// Response to "ruok" if the server is running: "imok" via the raw four letter word, while
+    /// the admin server mirrors the command.
+    pub ruok_reply: &'static str,
+    /// Default port of the admin server or None if the version has no admin server.
+    pub admin_port: Option<u16>,
+    /// Servers can be added and removed via "reconfig" without a rolling restart.
+    pub dynamic_reconfig: bool,
+    /// Four letter words except "srvr" have to be whitelisted in the zoo.cfg via
+    /// "4lw.commands.whitelist" to be answered on the client port.
+    pub four_letter_word_whitelist: bool,
+    pub metrics_format: MetricsFormat,
+}
+
+impl ZkCapabilities {
+    pub fn for_version(version: &ZookeeperVersion) -> Result<ZkCapabilities> {
+        Ok(ZkCapabilities::for_semver(&Version::parse(
+            &version.to_string(),
+        )?))
+    }
+
+    /// Up to 3.5.2 the standard four letter words are used, from 3.5.3 onwards the admin server
+    /// is queried via http.
+    pub fn for_semver(version: &Version) -> ZkCapabilities {
+        let admin_server = *version >= Version::new(3, 5, 3);
+
+        ZkCapabilities {
+            protocol: if admin_server {
+                Protocol::AdminServer
+            } else {
+                Protocol::FourLetterWord
+            },
+            ruok_reply: if admin_server { ARE_YOU_OK } else { I_AM_OK },
+            admin_port: if admin_server {
+                Some(DEFAULT_ADMIN_PORT)
+            } else {
+                None
+            },
+            dynamic_reconfig: *version >= Version::new(3, 5, 0),
+            four_letter_word_whitelist: *version >= Version::new(3, 5, 3),
+            metrics_format: if *version >= Version::new(3, 6, 0) {
+                MetricsFormat::Prometheus
+            } else {
+                MetricsFormat::JmxExporter
+            },
+        }
+    }
+}
@@ -1,43 +1,578 @@
-use crate::common::four_letter_commands::send_4lw_i_am_ok;
+use crate::common::capabilities::ZkCapabilities;
+use crate::common::discovery::ConnectString;
+use crate::common::four_letter_commands::{
+    get_server_id, send_4lw_i_am_ok, send_mntr, Mntr, ServerAccess, ServerState,
+};
+use crate::common::kube_api::KubeApi;
+use crate::common::metrics::{fetch_metrics, ZkMetrics};
+use crate::common::retry::{is_network_error, RetryPolicy};
+use crate::common::zk_client::{CreateMode, ZkClient};
+use crate::common::zoo_cfg::{ServerEntry, ZooCfg};
+use crate::common::zookeeper::{cluster_selector, ZkRoleGroup};
 use anyhow::{anyhow, Result};
-use integration_test_commons::test::kube::TestKubeClient;
 use integration_test_commons::test::prelude::{ConfigMap, ConfigMapVolumeSource, Pod};
+use k8s_openapi::api::core::v1::Service;
+use k8s_openapi::apimachinery::pkg::util::intstr::IntOrString;
+use stackable_operator::labels::{APP_ROLE_GROUP_LABEL, APP_VERSION_LABEL};
 use stackable_zookeeper_crd::ZookeeperVersion;
-use std::net::TcpStream;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
 
-/// Collect and gather all checks that may be performed on ZooKeeper server pods.
+/// Maximum time a znode written to one server may take to become visible on all other servers.
+const REPLICATION_TIMEOUT: u64 = 10;
+
+/// Interval between two reads while waiting for a znode to be replicated.
+const REPLICATION_POLL_INTERVAL_MILLIS: u64 = 100;
+
+/// Leader election may still be running when all pods are ready. Therefore the server roles
+/// are requested again until they are valid or this timeout (in seconds) is reached.
+const ENSEMBLE_ROLES_TIMEOUT: u64 = 30;
+
+/// Followers join the leader one after another after the ensemble changed, so the size of the
+/// ensemble is requested again until all are synced or this timeout (in seconds) is reached.
+const ENSEMBLE_SIZE_TIMEOUT: u64 = 60;
+
+/// Interval between two requests of the server states while waiting for a leader.
+const LEADER_POLL_INTERVAL_MILLIS: u64 = 500;
+
+/// Timeout in seconds for establishing a ZooKeeper session with a server.
+const CONNECT_TIMEOUT: u64 = 10;
+
+/// Timeout in seconds for requesting the server id if no myid is found in the configmaps.
+const SERVER_ID_TIMEOUT: u64 = 10;
+
+/// Timeout in seconds until the metrics port must accept connections.
+const METRICS_PORT_TIMEOUT: u64 = 10;
+
+/// Maximum difference between a count reported via the metrics and via "mntr". Both are
+/// requested one after another, so the values of a busy server may differ slightly.
+pub const METRICS_MNTR_TOLERANCE: u64 = 2;
+
+/// Timeout in seconds until the metrics of a server match its "mntr" statistics.
+const METRICS_MNTR_TIMEOUT: u64 = 30;
+
+/// Timeout in seconds until the endpoints of a service contain every ready pod it selects.
+const ENDPOINTS_TIMEOUT: u64 = 30;
+
+/// Timeout in seconds until the status of the custom resource reports the expected version.
+const CURRENT_VERSION_TIMEOUT: u64 = 30;
+
+/// Context of errors of `check_config_map` returned by `custom_checks`, e.g. to recognize
+/// failures caused by a known issue with the server.<id> entries.
+pub const CONFIG_MAP_CHECK_FAILED: &str = "Config map check failed";
+
+/// Error of `check_services` if the cluster has no services at all.
+pub const NO_SERVICE_FOUND: &str = "No service found";
+
+/// Collect and gather all checks that may be performed on ZooKeeper server pods. The commands
+/// are sent to the servers as described by the access (see `ServerAccess`).
 pub fn custom_checks(
-    client: &TestKubeClient,
+    client: &dyn KubeApi,
     pods: &[Pod],
-    version: &ZookeeperVersion,
-    client_port: u16,
+    access: &ServerAccess,
     expected_pod_count: usize,
 ) -> Result<()> {
     for pod in pods {
-        send_4lw_i_am_ok(pod, version, client_port)?;
-        check_config_map(client, pod, expected_pod_count)?;
+        send_4lw_i_am_ok(pod, access)?;
+        check_config_map(client, pod, pods, access, expected_pod_count).map_err(|err| {
+            err.context(format!(
+                "{} for pod [{}]",
+                CONFIG_MAP_CHECK_FAILED,
+                pod.metadata.name.as_ref().unwrap()
+            ))
+        })?;
     }
+    check_ensemble_roles(pods, access)?;
     Ok(())
 }
 
 /// Collect and gather all checks with regard to metrics and container ports.
 pub fn custom_monitoring_checks(
     pods: &[Pod],
+    version: &ZookeeperVersion,
     container_ports: &[(&str, u16)],
     container_name: &str,
 ) -> Result<()> {
     for pod in pods {
         check_container_ports(pod, container_ports, container_name)?;
-        check_metrics_port_open(pod, container_name)?;
+        check_metrics(pod, container_name, version)?;
+    }
+    Ok(())
+}
+
+/// Write a uniquely named ephemeral znode through the first pod and read it back through every
+/// pod. Each server syncs with the leader before reading, so this proves that all servers are
+/// part of the same ensemble and serve its data.
+pub fn check_znode_round_trip(pods: &[Pod], client_port: u16) -> Result<()> {
+    let writer_pod = pods
+        .first()
+        .ok_or_else(|| anyhow!("No pods provided for the znode round trip check"))?;
+
+    let path = format!("/integration-test-{}", Uuid::new_v4());
+    let data = Uuid::new_v4().to_string();
+
+    let mut writer = connect(writer_pod, client_port)?;
+    // ephemeral, so the znode is removed with the session of the writer even if the check fails
+    writer.create(&path, data.as_bytes(), CreateMode::Ephemeral)?;
+
+    let read_back = pods.iter().try_for_each(|pod| {
+        let mut reader = connect(pod, client_port)?;
+        reader.sync(&path)?;
+        let (received, _) = reader.get_data(&path)?;
+        reader.close()?;
+
+        if received != data.as_bytes() {
+            return Err(anyhow!(
+                "Znode [{}] read via pod [{}] contains [{}] instead of [{}]",
+                path,
+                pod.metadata.name.as_ref().unwrap(),
+                String::from_utf8_lossy(&received),
+                data
+            ));
+        }
+        Ok(())
+    });
+
+    // closing the writer removes the znode
+    let closed = writer.close();
+    read_back?;
+    closed
+}
+
+/// Write the given number of uniquely named znodes through the first pod, e.g. to verify later
+/// that they survived an upgrade via `check_znodes`. Returns path and data of every znode.
+pub fn write_znodes(
+    pods: &[Pod],
+    client_port: u16,
+    count: usize,
+) -> Result<Vec<(String, Vec<u8>)>> {
+    let writer_pod = pods
+        .first()
+        .ok_or_else(|| anyhow!("No pods provided for writing znodes"))?;
+
+    let mut writer = connect(writer_pod, client_port)?;
+    let root = format!("/integration-test-data-{}", Uuid::new_v4());
+    writer.create(&root, &[], CreateMode::Persistent)?;
+
+    let mut znodes = Vec::new();
+    for index in 0..count {
+        let path = format!("{}/znode-{}", root, index);
+        let data = Uuid::new_v4().to_string().into_bytes();
+        writer.create(&path, &data, CreateMode::Persistent)?;
+        znodes.push((path, data));
+    }
+
+    writer.close()?;
+    Ok(znodes)
+}
+
+/// Read every znode through every pod (after a sync with the leader) and compare its data.
+pub fn check_znodes(pods: &[Pod], client_port: u16, znodes: &[(String, Vec<u8>)]) -> Result<()> {
+    for pod in pods {
+        let pod_name = pod.metadata.name.as_ref().unwrap();
+        let mut reader = connect(pod, client_port)?;
+
+        for (path, data) in znodes {
+            reader.sync(path)?;
+            let (received, _) = reader.get_data(path).map_err(|err| {
+                anyhow!(
+                    "Znode [{}] not readable via pod [{}]: {}",
+                    path,
+                    pod_name,
+                    err
+                )
+            })?;
+            if &received != data {
+                return Err(anyhow!(
+                    "Znode [{}] read via pod [{}] contains [{}] instead of [{}]",
+                    path,
+                    pod_name,
+                    String::from_utf8_lossy(&received),
+                    String::from_utf8_lossy(data)
+                ));
+            }
+        }
+
+        reader.close()?;
+    }
+
+    Ok(())
+}
+
+/// Write a uniquely named ephemeral znode through the first pod and poll every other pod until
+/// the znode becomes visible, without forcing a sync. Fails if any server does not see the znode
+/// within REPLICATION_TIMEOUT, e.g. because the servers run standalone instead of as an ensemble.
+/// Returns the propagation latency per pod name.
+pub fn check_replication(pods: &[Pod], client_port: u16) -> Result<Vec<(String, Duration)>> {
+    let (writer_pod, reader_pods) = pods
+        .split_first()
+        .ok_or_else(|| anyhow!("No pods provided for the replication check"))?;
+
+    // connect before writing so the connection setup is not measured as latency
+    let mut readers = Vec::new();
+    for pod in reader_pods {
+        readers.push((
+            pod.metadata.name.clone().unwrap(),
+            connect(pod, client_port)?,
+        ));
+    }
+
+    let path = format!("/integration-test-replication-{}", Uuid::new_v4());
+    let mut writer = connect(writer_pod, client_port)?;
+    // ephemeral, so the znode is removed with the session of the writer even if the check fails
+    writer.create(&path, &[], CreateMode::Ephemeral)?;
+
+    let description = format!(
+        "Znode [{}] written via pod [{}]",
+        path,
+        writer_pod.metadata.name.as_ref().unwrap()
+    );
+    let replicated = wait_for_replication(
+        &description,
+        &mut readers,
+        Duration::from_secs(REPLICATION_TIMEOUT),
+        |reader| Ok(reader.exists(&path)?.is_some()),
+    );
+
+    // close every session before a failure is returned, closing the writer removes the znode
+    let mut closed = Ok(());
+    for (_, reader) in readers {
+        closed = closed.and(reader.close());
+    }
+    closed = closed.and(writer.close());
+    let latencies = replicated?;
+    closed?;
+
+    for (pod_name, latency) in &latencies {
+        println!(
+            "Znode [{}] replicated to pod [{}] after [{}] ms",
+            path,
+            pod_name,
+            latency.as_millis()
+        );
+    }
+
+    Ok(latencies)
+}
+
+/// Poll every reader until `is_replicated` returns true for it and return the latency per
+/// reader name, measured from the call of this function. A reader whose poll fails stays
+/// pending and is polled again on the next attempt.
+pub fn wait_for_replication<R, F>(
+    description: &str,
+    readers: &mut [(String, R)],
+    timeout: Duration,
+    mut is_replicated: F,
+) -> Result<Vec<(String, Duration)>>
+where
+    F: FnMut(&mut R) -> Result<bool>,
+{
+    let written = Instant::now();
+    let mut pending: Vec<usize> = (0..readers.len()).collect();
+    let mut latencies = Vec::new();
+
+    RetryPolicy::new(timeout)
+        .initial_delay(Duration::from_millis(REPLICATION_POLL_INTERVAL_MILLIS))
+        .backoff_factor(1.0)
+        .jitter(0.0)
+        .retry(&format!("replication of {}", description), || {
+            let mut index = 0;
+            while index < pending.len() {
+                let (name, reader) = &mut readers[pending[index]];
+                if is_replicated(reader)? {
+                    latencies.push((name.clone(), written.elapsed()));
+                    pending.remove(index);
+                } else {
+                    index += 1;
+                }
+            }
+
+            if pending.is_empty() {
+                Ok(())
+            } else {
+                Err(anyhow!(
+                    "{} is not yet replicated to pods {:?}",
+                    description,
+                    pending
+                        .iter()
+                        .map(|index| &readers[*index].0)
+                        .collect::<Vec<_>>()
+                ))
+            }
+        })?;
+
+    Ok(latencies)
+}
+
+/// Request the server state of every pod via "mntr" and verify the roles with
+/// `verify_ensemble_roles`.
+pub fn check_ensemble_roles(pods: &[Pod], access: &ServerAccess) -> Result<()> {
+    RetryPolicy::new(Duration::from_secs(ENSEMBLE_ROLES_TIMEOUT)).retry("ensemble roles", || {
+        verify_ensemble_roles(&get_server_states(pods, access)?)
+    })
+}
+
+/// Request "mntr" from every pod and verify with `verify_ensemble_size` that the leader reports
+/// all other servers as synced followers.
+pub fn check_ensemble_size(pods: &[Pod], access: &ServerAccess) -> Result<()> {
+    RetryPolicy::new(Duration::from_secs(ENSEMBLE_SIZE_TIMEOUT)).retry("ensemble size", || {
+        let mut mntrs = Vec::new();
+        for pod in pods {
+            let mntr = send_mntr(access, node_name(pod)?)?;
+            mntrs.push((pod.metadata.name.clone().unwrap(), mntr));
+        }
+        verify_ensemble_size(&mntrs)
+    })
+}
+
+/// The leader of an ensemble must report every other server as follower (zk_followers) which
+/// is in sync (zk_synced_followers). Single servers have no followers.
+pub fn verify_ensemble_size(mntrs: &[(String, Mntr)]) -> Result<()> {
+    if mntrs.len() == 1 {
+        return Ok(());
+    }
+
+    let leader = match mntrs
+        .iter()
+        .filter(|(_, mntr)| mntr.server_state == ServerState::Leader)
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        [leader] => *leader,
+        leaders => {
+            return Err(anyhow!(
+                "Expected exactly one leader in ensemble of [{}] servers but found [{}]",
+                mntrs.len(),
+                leaders.len()
+            ))
+        }
+    };
+
+    let (pod_name, mntr) = leader;
+    let expected = mntrs.len() as u64 - 1;
+    if mntr.followers != Some(expected) || mntr.synced_followers != Some(expected) {
+        return Err(anyhow!(
+            "Leader [{}] reports [{:?}] followers and [{:?}] synced followers instead of [{}]",
+            pod_name,
+            mntr.followers,
+            mntr.synced_followers,
+            expected
+        ));
+    }
+
+    Ok(())
+}
+
+/// Request the server state of every pod via "mntr". Returns pairs of pod name and state.
+pub fn get_server_states(
+    pods: &[Pod],
+    access: &ServerAccess,
+) -> Result<Vec<(String, ServerState)>> {
+    let mut states = Vec::new();
+    for pod in pods {
+        let mntr = send_mntr(access, node_name(pod)?)?;
+        states.push((pod.metadata.name.clone().unwrap(), mntr.server_state));
+    }
+    Ok(states)
+}
+
+/// A single server must run standalone. An ensemble of several servers must have exactly one
+/// leader while all other servers are followers.
+pub fn verify_ensemble_roles(states: &[(String, ServerState)]) -> Result<()> {
+    if states.len() == 1 {
+        let (pod_name, state) = &states[0];
+        return if *state == ServerState::Standalone {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "Single server [{}] reports [{}] instead of [{}]",
+                pod_name,
+                state,
+                ServerState::Standalone
+            ))
+        };
+    }
+
+    let leaders: Vec<&String> = states
+        .iter()
+        .filter(|(_, state)| *state == ServerState::Leader)
+        .map(|(pod_name, _)| pod_name)
+        .collect();
+
+    if leaders.len() != 1 {
+        return Err(anyhow!(
+            "Expected exactly one leader in ensemble of [{}] servers but found {:?}",
+            states.len(),
+            leaders
+        ));
+    }
+
+    for (pod_name, state) in states {
+        if *state != ServerState::Leader && *state != ServerState::Follower {
+            return Err(anyhow!(
+                "Server [{}] reports [{}] but must be [{}] or [{}] in an ensemble",
+                pod_name,
+                state,
+                ServerState::Leader,
+                ServerState::Follower
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// The name of the leader of a valid ensemble (see `verify_ensemble_roles`).
+pub fn find_leader(states: &[(String, ServerState)]) -> Result<String> {
+    verify_ensemble_roles(states)?;
+    states
+        .iter()
+        .find(|(_, state)| *state == ServerState::Leader)
+        .map(|(pod_name, _)| pod_name.clone())
+        .ok_or_else(|| anyhow!("No leader found in {:?}", states))
+}
+
+/// Request the server states of the pods until they form a valid ensemble and return the name
+/// of its leader. Servers do not answer "mntr" while an election is running. The states are
+/// polled at a constant interval, so the caller can measure how long the election took.
+pub fn wait_for_leader(pods: &[Pod], access: &ServerAccess, timeout: Duration) -> Result<String> {
+    RetryPolicy::new(timeout)
+        .initial_delay(Duration::from_millis(LEADER_POLL_INTERVAL_MILLIS))
+        .backoff_factor(1.0)
+        .retry("leader election", || {
+            find_leader(&get_server_states(pods, access)?)
+        })
+}
+
+/// The pod must have been replaced: none of the pods is the deleted one (i.e. has its uid),
+/// while the expected number of pods exists and all of them are ready.
+pub fn verify_pod_replaced(deleted: &Pod, pods: &[Pod], expected_pod_count: usize) -> Result<()> {
+    let deleted_name = deleted.metadata.name.clone().unwrap_or_default();
+    if pods
+        .iter()
+        .any(|pod| pod.metadata.uid.is_some() && pod.metadata.uid == deleted.metadata.uid)
+    {
+        return Err(anyhow!("Pod [{}] still exists", deleted_name));
+    }
+
+    if pods.len() != expected_pod_count {
+        return Err(anyhow!(
+            "[{}] pods exist after deleting [{}] instead of [{}]",
+            pods.len(),
+            deleted_name,
+            expected_pod_count
+        ));
+    }
+
+    let not_ready: Vec<String> = pods
+        .iter()
+        .filter(|pod| !is_ready(pod))
+        .map(|pod| pod.metadata.name.clone().unwrap_or_default())
+        .collect();
+    if !not_ready.is_empty() {
+        return Err(anyhow!(
+            "Pods {:?} are not ready after deleting [{}]",
+            not_ready,
+            deleted_name
+        ));
+    }
+
+    Ok(())
+}
+
+/// Connect to the ZooKeeper server of a pod. Network errors are retried because the server may
+/// not accept client connections yet.
+fn connect(pod: &Pod, client_port: u16) -> Result<ZkClient> {
+    let host = node_address(pod, client_port)?;
+    RetryPolicy::new(Duration::from_secs(CONNECT_TIMEOUT))
+        .retry_if(is_network_error)
+        .retry(&format!("connect to {}", host), || ZkClient::connect(&host))
+}
+
+/// Build the address (format: <node_name>:<port>) of a pod.
+pub fn node_address(pod: &Pod, port: u16) -> Result<String> {
+    Ok(format!("{}:{}", node_name(pod)?, port))
+}
+
+/// The name of the node the pod is scheduled on.
+pub fn node_name(pod: &Pod) -> Result<&str> {
+    match &pod.spec.as_ref().unwrap().node_name {
+        Some(node_name) => Ok(node_name),
+        None => Err(anyhow!(
+            "Missing node_name in pod [{}]. Cannot create host address!",
+            pod.metadata.name.as_ref().unwrap(),
+        )),
+    }
+}
+
+/// Perform checks on a connect string of the cluster (see `connect_string_for_pods`):
+/// - the listed servers match the nodes of the pods on the client port
+/// - a session can be established with every listed server
+pub fn check_connect_string(
+    connect_string: &ConnectString,
+    pods: &[Pod],
+    client_port: u16,
+) -> Result<()> {
+    verify_connect_string_hosts(connect_string, pods, client_port)?;
+
+    for address in connect_string.addresses() {
+        let zk = RetryPolicy::new(Duration::from_secs(CONNECT_TIMEOUT))
+            .retry_if(is_network_error)
+            .retry(&format!("connect to {}", address), || {
+                ZkClient::connect(&address)
+            })
+            .map_err(|err| {
+                anyhow!(
+                    "Server [{}] of the connect string [{}] is not reachable: {}",
+                    address,
+                    connect_string,
+                    err
+                )
+            })?;
+        zk.close()?;
+    }
+
+    Ok(())
+}
+
+/// The servers of the connect string must be the node addresses (see `node_address`) of the
+/// pods, each listed exactly once.
+pub fn verify_connect_string_hosts(
+    connect_string: &ConnectString,
+    pods: &[Pod],
+    client_port: u16,
+) -> Result<()> {
+    let mut listed = connect_string.addresses();
+    listed.sort();
+    let mut expected = pods
+        .iter()
+        .map(|pod| node_address(pod, client_port))
+        .collect::<Result<Vec<_>>>()?;
+    expected.sort();
+
+    if listed != expected {
+        return Err(anyhow!(
+            "Connect string [{}] lists the servers {:?} instead of the ZooKeeper pods {:?}",
+            connect_string,
+            listed,
+            expected
+        ));
     }
     Ok(())
 }
 
 /// Perform checks on configmaps for:
 /// - server.<id> property set correctly (especially with scale up / down)
+/// - server.<id> hosts match the node names of the pods
+/// - the myid of the pod matches the id of the server.<id> property on its node
+///
+/// The server id is requested via the access if no myid is found in the configmaps.
 pub fn check_config_map(
-    client: &TestKubeClient,
+    client: &dyn KubeApi,
     pod: &Pod,
+    pods: &[Pod],
+    access: &ServerAccess,
     expected_server_count: usize,
 ) -> Result<()> {
     let config_cm_name = get_config_cm(
@@ -45,9 +580,430 @@ pub fn check_config_map(
         pod,
         stackable_operator::configmap::CONFIGMAP_TYPE_LABEL,
     )?;
-    let config_map: Option<ConfigMap> = client.find_namespaced(&config_cm_name);
+    let config_map = client.find_config_map(&config_cm_name);
+
+    let servers = check_for_server_id_property_count(config_map, expected_server_count)?;
+    check_server_hosts(&servers, pods)?;
+
+    let myid = match get_myid_from_config_maps(client, pod)? {
+        Some(myid) => myid,
+        None => {
+            let host_name = node_name(pod)?;
+            RetryPolicy::new(Duration::from_secs(SERVER_ID_TIMEOUT))
+                .retry_if(is_network_error)
+                .retry(&format!("server id of {}", host_name), || {
+                    get_server_id(access, host_name)
+                })?
+        }
+    };
+    check_server_id(&servers, pod, myid)
+}
+
+/// Every server.<id> host must be the node name of one of the provided pods.
+/// Vice versa every node name of the pods must be listed exactly once.
+pub fn check_server_hosts(servers: &[ServerEntry], pods: &[Pod]) -> Result<()> {
+    let node_names: Vec<&String> = pods
+        .iter()
+        .filter_map(|pod| pod.spec.as_ref().unwrap().node_name.as_ref())
+        .collect();
+
+    for server in servers {
+        if !node_names.contains(&&server.host) {
+            return Err(anyhow!(
+                "Host of [{}] does not match any node name of the ZooKeeper pods {:?}",
+                server,
+                node_names
+            ));
+        }
+    }
+
+    for node_name in node_names {
+        let count = servers
+            .iter()
+            .filter(|server| &server.host == node_name)
+            .count();
+        if count != 1 {
+            return Err(anyhow!(
+                "Node [{}] of a ZooKeeper pod is listed [{}] times in the server.<id> properties instead of once",
+                node_name,
+                count
+            ));
+        }
+    }
 
-    check_for_server_id_property_count(config_map, expected_server_count)
+    Ok(())
+}
+
+/// Check that every role group has the expected number of pods and that these pods run on
+/// nodes matching the node selector of the role group.
+pub fn check_role_group_placement(
+    client: &dyn KubeApi,
+    pods: &[Pod],
+    role_groups: &BTreeMap<String, ZkRoleGroup>,
+) -> Result<()> {
+    for (role_group_name, role_group) in role_groups {
+        let role_group_pods: Vec<&Pod> = pods
+            .iter()
+            .filter(|pod| pod.metadata.labels.get(APP_ROLE_GROUP_LABEL) == Some(role_group_name))
+            .collect();
+
+        if role_group_pods.len() != role_group.replicas() {
+            return Err(anyhow!(
+                "Role group [{}] has [{}] pods but [{}] replicas are expected",
+                role_group_name,
+                role_group_pods.len(),
+                role_group.replicas()
+            ));
+        }
+
+        for pod in role_group_pods {
+            let pod_name = pod.metadata.name.as_ref().unwrap();
+            let node_name = pod
+                .spec
+                .as_ref()
+                .unwrap()
+                .node_name
+                .as_ref()
+                .ok_or_else(|| anyhow!("Pod [{}] is not scheduled on any node", pod_name))?;
+            let node = client
+                .find_node(node_name)
+                .ok_or_else(|| anyhow!("Node [{}] of pod [{}] not found", node_name, pod_name))?;
+
+            for (key, value) in role_group.selector() {
+                if node.metadata.labels.get(&key) != Some(&value) {
+                    return Err(anyhow!(
+                        "Pod [{}] of role group [{}] runs on node [{}] which does not match the selector label [{}={}]",
+                        pod_name,
+                        role_group_name,
+                        node_name,
+                        key,
+                        value
+                    ));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The server.<id> property with the id (myid) of the pod must point to the node of the pod.
+pub fn check_server_id(servers: &[ServerEntry], pod: &Pod, myid: u64) -> Result<()> {
+    let pod_name = pod.metadata.name.as_ref().unwrap();
+    let node_name = pod.spec.as_ref().unwrap().node_name.as_ref();
+
+    match servers.iter().find(|server| server.id == myid) {
+        None => Err(anyhow!(
+            "Pod [{}] has myid [{}] but there is no server.{} property",
+            pod_name,
+            myid,
+            myid
+        )),
+        Some(server) if Some(&server.host) != node_name => Err(anyhow!(
+            "Pod [{}] on node [{:?}] has myid [{}] but [{}] points to another host",
+            pod_name,
+            node_name,
+            myid,
+            server
+        )),
+        Some(_) => Ok(()),
+    }
+}
+
+/// Search the configmaps mounted into the pod for the "myid" file.
+fn get_myid_from_config_maps(client: &dyn KubeApi, pod: &Pod) -> Result<Option<u64>> {
+    for volume in &pod.spec.as_ref().unwrap().volumes {
+        if let Some(ConfigMapVolumeSource {
+            name: Some(cm_name),
+            ..
+        }) = &volume.config_map
+        {
+            if let Some(config_map) = client.find_config_map(cm_name) {
+                if let Some(myid) = config_map.data.get("myid") {
+                    return myid.trim().parse().map(Some).map_err(|_| {
+                        anyhow!("Invalid myid [{}] in configmap [{}]", myid, cm_name)
+                    });
+                }
+            }
+        }
+    }
+
+    Ok(None)
+}
+
+/// Every pod must be labelled with the given ZooKeeper version.
+pub fn check_pod_versions(pods: &[Pod], version: &ZookeeperVersion) -> Result<()> {
+    for pod in pods {
+        let pod_version = pod.metadata.labels.get(APP_VERSION_LABEL);
+        if pod_version != Some(&version.to_string()) {
+            return Err(anyhow!(
+                "Pod [{}] runs version [{:?}] instead of [{}]",
+                pod.metadata.name.as_ref().unwrap(),
+                pod_version,
+                version
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// The status of the custom resource must report the given version as current version.
+pub fn check_current_version(
+    client: &dyn KubeApi,
+    name: &str,
+    version: &ZookeeperVersion,
+) -> Result<()> {
+    RetryPolicy::new(Duration::from_secs(CURRENT_VERSION_TIMEOUT)).retry(
+        &format!("current version of [{}]", name),
+        || {
+            let cluster = client
+                .find_zookeeper_cluster(name)
+                .ok_or_else(|| anyhow!("ZookeeperCluster [{}] not found", name))?;
+            let status = serde_json::to_value(&cluster)?["status"].clone();
+
+            if status["currentVersion"] != version.to_string() {
+                return Err(anyhow!(
+                    "ZookeeperCluster [{}] reports current version [{}] instead of [{}] (conditions: {})",
+                    name,
+                    status["currentVersion"],
+                    version,
+                    status["conditions"]
+                ));
+            }
+            Ok(())
+        },
+    )
+}
+
+/// Perform checks on the services of the cluster:
+/// - at least one service is labelled with the cluster and its name starts with the cluster name
+/// - the selector of every service only matches pods of the cluster
+/// - every service port targets a container port of the selected pods
+/// - every given container port (see `check_container_ports`) is exposed by a service
+/// - the endpoints of every service contain each ready pod it selects
+pub fn check_services(
+    client: &dyn KubeApi,
+    cluster_name: &str,
+    pods: &[Pod],
+    container_ports: &[(&str, u16)],
+    container_name: &str,
+) -> Result<()> {
+    let services = client.list_services(&cluster_selector(cluster_name));
+    if services.is_empty() {
+        return Err(anyhow!(
+            "{} for ZooKeeper cluster [{}]",
+            NO_SERVICE_FOUND,
+            cluster_name
+        ));
+    }
+
+    let mut exposed_ports = Vec::new();
+    for service in &services {
+        let service_name = service.metadata.name.clone().unwrap_or_default();
+        if !service_name.starts_with(cluster_name) {
+            return Err(anyhow!(
+                "Service [{}] is not named after the ZooKeeper cluster [{}]",
+                service_name,
+                cluster_name
+            ));
+        }
+
+        let selected_pods = check_service_selector(client, service, pods)?;
+        exposed_ports.extend(check_service_ports(
+            service,
+            &selected_pods,
+            container_name,
+        )?);
+    }
+
+    for (name, number) in container_ports {
+        if !exposed_ports.contains(&(name.to_string(), *number)) {
+            return Err(anyhow!(
+                "Container port [{}] ([{}]) is not exposed by any service of the ZooKeeper cluster [{}]. Exposed: {:?}",
+                name,
+                number,
+                cluster_name,
+                exposed_ports
+            ));
+        }
+    }
+
+    // the endpoints controller needs some time to pick up pods which just became ready
+    RetryPolicy::new(Duration::from_secs(ENDPOINTS_TIMEOUT)).retry("service endpoints", || {
+        for service in &services {
+            check_endpoints(client, service, pods)?;
+        }
+        Ok(())
+    })
+}
+
+/// The selector of the service must not be empty and only match the given pods. Returns the
+/// selected pods.
+pub fn check_service_selector(
+    client: &dyn KubeApi,
+    service: &Service,
+    pods: &[Pod],
+) -> Result<Vec<Pod>> {
+    let service_name = service.metadata.name.clone().unwrap_or_default();
+    let selector = service
+        .spec
+        .as_ref()
+        .map(|spec| spec.selector.clone())
+        .unwrap_or_default();
+    if selector.is_empty() {
+        return Err(anyhow!("Service [{}] has no selector", service_name));
+    }
+
+    let label_selector = selector
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, value))
+        .collect::<Vec<_>>()
+        .join(",");
+    let selected_pods = client.list_pods(&label_selector);
+    if selected_pods.is_empty() {
+        return Err(anyhow!(
+            "Selector [{}] of service [{}] does not match any pod",
+            label_selector,
+            service_name
+        ));
+    }
+
+    for pod in &selected_pods {
+        if !pods.iter().any(|p| p.metadata.name == pod.metadata.name) {
+            return Err(anyhow!(
+                "Selector [{}] of service [{}] matches the foreign pod [{}]",
+                label_selector,
+                service_name,
+                pod.metadata.name.as_ref().unwrap()
+            ));
+        }
+    }
+
+    Ok(selected_pods)
+}
+
+/// Every port of the service must target a port of the given container in every selected pod.
+/// Returns the name and number of every targeted container port.
+pub fn check_service_ports(
+    service: &Service,
+    selected_pods: &[Pod],
+    container_name: &str,
+) -> Result<Vec<(String, u16)>> {
+    let service_name = service.metadata.name.clone().unwrap_or_default();
+    let service_ports = service
+        .spec
+        .as_ref()
+        .map(|spec| spec.ports.clone())
+        .unwrap_or_default();
+    if service_ports.is_empty() {
+        return Err(anyhow!("Service [{}] has no ports", service_name));
+    }
+
+    let mut targeted = Vec::new();
+    for service_port in &service_ports {
+        for pod in selected_pods {
+            let container_ports = pod
+                .spec
+                .as_ref()
+                .and_then(|spec| {
+                    spec.containers
+                        .iter()
+                        .find(|container| container.name == container_name)
+                })
+                .map(|container| container.ports.clone())
+                .unwrap_or_default();
+
+            // without a target port the service port is forwarded to the same port number
+            let container_port = container_ports
+                .iter()
+                .find(|container_port| match &service_port.target_port {
+                    Some(IntOrString::Int(number)) => container_port.container_port == *number,
+                    Some(IntOrString::String(name)) => container_port.name.as_ref() == Some(name),
+                    None => container_port.container_port == service_port.port,
+                });
+
+            match container_port {
+                Some(container_port) => {
+                    let target = (
+                        container_port.name.clone().unwrap_or_default(),
+                        container_port.container_port as u16,
+                    );
+                    if !targeted.contains(&target) {
+                        targeted.push(target);
+                    }
+                }
+                None => {
+                    return Err(anyhow!(
+                        "Port [{:?}] ([{}]) of service [{}] targets [{:?}] which is no port of container [{}] in pod [{}]",
+                        service_port.name,
+                        service_port.port,
+                        service_name,
+                        service_port.target_port,
+                        container_name,
+                        pod.metadata.name.as_ref().unwrap()
+                    ))
+                }
+            }
+        }
+    }
+
+    Ok(targeted)
+}
+
+/// The endpoints of the service must list every ready pod selected by the service as ready
+/// address and expose every port of the service.
+pub fn check_endpoints(client: &dyn KubeApi, service: &Service, pods: &[Pod]) -> Result<()> {
+    let service_name = service.metadata.name.clone().unwrap_or_default();
+    let endpoints = client
+        .find_endpoints(&service_name)
+        .ok_or_else(|| anyhow!("No endpoints found for service [{}]", service_name))?;
+
+    let ready_addresses: Vec<&String> = endpoints
+        .subsets
+        .iter()
+        .flat_map(|subset| subset.addresses.iter())
+        .filter_map(|address| address.target_ref.as_ref()?.name.as_ref())
+        .collect();
+    let endpoint_ports: Vec<Option<String>> = endpoints
+        .subsets
+        .iter()
+        .flat_map(|subset| subset.ports.iter())
+        .map(|port| port.name.clone())
+        .collect();
+
+    let selected_pods = check_service_selector(client, service, pods)?;
+    for pod in selected_pods.iter().filter(|pod| is_ready(pod)) {
+        let pod_name = pod.metadata.name.as_ref().unwrap();
+        if !ready_addresses.contains(&pod_name) {
+            return Err(anyhow!(
+                "Ready pod [{}] is missing in the endpoints of service [{}]: {:?}",
+                pod_name,
+                service_name,
+                ready_addresses
+            ));
+        }
+    }
+
+    for service_port in service.spec.iter().flat_map(|spec| spec.ports.iter()) {
+        if !endpoint_ports.contains(&service_port.name) {
+            return Err(anyhow!(
+                "Port [{:?}] of service [{}] is missing in its endpoints: {:?}",
+                service_port.name,
+                service_name,
+                endpoint_ports
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// A pod is ready if its "Ready" condition is true.
+fn is_ready(pod: &Pod) -> bool {
+    pod.status
+        .iter()
+        .flat_map(|status| status.conditions.iter())
+        .any(|condition| condition.type_ == "Ready" && condition.status == "True")
 }
 
 /// Check if container ports with given name and port number are set in the pod.
@@ -77,15 +1033,119 @@ pub fn check_container_ports(
             found
         });
 
-    return if got_port_count == container_ports.len() {
+    if got_port_count == container_ports.len() {
         Ok(())
     } else {
         Err(anyhow!("Required container_ports in container [{}] do not match the specified pod container ports. Required [{}] vs provided [{}]",
         container_name, container_ports.len(), got_port_count))
-    };
+    }
 }
 
-pub fn check_metrics_port_open(pod: &Pod, container_name: &str) -> Result<()> {
+/// Fetch the metrics of the pod from the port named "metrics" of the given container and check
+/// them with `check_metrics_endpoint`.
+pub fn check_metrics(
+    pod: &Pod,
+    container_name: &str,
+    version: &ZookeeperVersion,
+) -> Result<ZkMetrics> {
+    let address = metrics_address(pod, container_name)?;
+    RetryPolicy::new(Duration::from_secs(METRICS_PORT_TIMEOUT))
+        .retry(&format!("metrics of {}", address), || {
+            check_metrics_endpoint(&address, version)
+        })
+}
+
+/// The metrics endpoint (format: <host>:<port>) must serve valid Prometheus metrics which
+/// contain the key ZooKeeper metrics in the format of the version (see `ZkCapabilities`).
+pub fn check_metrics_endpoint(address: &str, version: &ZookeeperVersion) -> Result<ZkMetrics> {
+    let metrics = fetch_metrics(address)?;
+    let format = ZkCapabilities::for_version(version)?.metrics_format;
+    ZkMetrics::from_metrics(&metrics, format)
+        .map_err(|err| anyhow!("Invalid metrics of [{}]: {}", address, err))
+}
+
+/// The metrics of every pod must report the same values as "mntr" (see
+/// `verify_metrics_match_mntr`).
+pub fn check_metrics_match_mntr(
+    pods: &[Pod],
+    container_name: &str,
+    access: &ServerAccess,
+) -> Result<()> {
+    for pod in pods {
+        let host_name = node_name(pod)?;
+        let metrics_address = metrics_address(pod, container_name)?;
+        RetryPolicy::new(Duration::from_secs(METRICS_MNTR_TIMEOUT)).retry(
+            &format!("metrics of {} match mntr", metrics_address),
+            || compare_metrics_with_mntr(access, host_name, &metrics_address),
+        )?;
+    }
+    Ok(())
+}
+
+/// Request "mntr" from the server running on host_name and the metrics of the same server
+/// (format: <host>:<port>) and compare them with `verify_metrics_match_mntr`.
+pub fn compare_metrics_with_mntr(
+    access: &ServerAccess,
+    host_name: &str,
+    metrics_address: &str,
+) -> Result<()> {
+    let mntr = send_mntr(access, host_name)?;
+    let metrics = check_metrics_endpoint(metrics_address, &access.version)?;
+    verify_metrics_match_mntr(&mntr, &metrics, METRICS_MNTR_TOLERANCE)
+        .map_err(|err| anyhow!("Metrics of [{}] diverge: {}", metrics_address, err))
+}
+
+/// The znode count and outstanding requests may differ by the tolerance, while the server
+/// state and the follower count have to be equal. Values which are not reported by the
+/// metrics format are skipped, i.e. the follower count of the JMX exporter format (see
+/// `ZkMetrics::followers`). All divergences are reported at once.
+pub fn verify_metrics_match_mntr(mntr: &Mntr, metrics: &ZkMetrics, tolerance: u64) -> Result<()> {
+    let mut divergences = Vec::new();
+
+    for (name, from_mntr, from_metrics) in &[
+        ("znode count", mntr.znode_count, metrics.znode_count),
+        (
+            "outstanding requests",
+            mntr.outstanding_requests,
+            metrics.outstanding_requests,
+        ),
+    ] {
+        let difference = (*from_mntr as i64 - *from_metrics as i64).unsigned_abs();
+        if difference > tolerance {
+            divergences.push(format!(
+                "{}: mntr [{}] vs metrics [{}] (tolerance [{}])",
+                name, from_mntr, from_metrics, tolerance
+            ));
+        }
+    }
+
+    if let Some(state) = metrics.server_state {
+        if state != mntr.server_state {
+            divergences.push(format!(
+                "server state: mntr [{}] vs metrics [{}]",
+                mntr.server_state, state
+            ));
+        }
+    }
+
+    if let Some(followers) = metrics.followers {
+        if Some(followers) != mntr.followers {
+            divergences.push(format!(
+                "followers: mntr [{:?}] vs metrics [{}]",
+                mntr.followers, followers
+            ));
+        }
+    }
+
+    if divergences.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow!("{}", divergences.join(", ")))
+    }
+}
+
+/// The address (format: <node name>:<port>) of the port named "metrics" of the given container.
+fn metrics_address(pod: &Pod, container_name: &str) -> Result<String> {
     let container_port_name = "metrics";
     // extract hostname from port
     let node_name = match &pod.spec.as_ref().unwrap().node_name {
@@ -127,54 +1187,41 @@ pub fn check_metrics_port_open(pod: &Pod, container_name: &str) -> Result<()> {
         }
     };
 
-    scan_port(&format!("{}:{}", node_name, port))
+    Ok(format!("{}:{}", node_name, port))
 }
 
-/// Scan port of an address.
-pub fn scan_port(address: &str) -> Result<()> {
-    match TcpStream::connect(address) {
-        Ok(_) => Ok(()),
-        Err(err) => Err(anyhow!(
-            "TCP error occurred when connecting to [{}]: {}",
-            address,
-            err.to_string()
-        )),
-    }
-}
-
-/// This is a simple check for the correctness of the server property in config maps.
+/// Check the server properties of the zoo.cfg in config maps.
 /// Every known server will be registered like:
-/// server.1 = some_url
-/// server.2 = another_url
+/// server.1 = some_url:2888:3888
+/// server.2 = another_url:2888:3888
 /// If pods crash or scaling appears we have to make sure that the config maps
 /// and pods are updated / restarted in order to contain the correct state of the cluster.
+/// The server ids must be unique, the ports well-formed and the count must match exactly.
+/// Returns the parsed server.<id> properties.
 fn check_for_server_id_property_count(
     cm: Option<ConfigMap>,
     expected_server_count: usize,
-) -> Result<()> {
-    let mut server_count: usize = 0;
-    if let Some(config_map) = cm {
-        let data = config_map.data;
-
-        // TODO: This might interfere with other properties having the "server."
-        //    string contained. Needs more stable solution.
-        if let Some(value) = data.get("zoo.cfg") {
-            server_count = value.matches("server.").count();
-        }
+) -> Result<Vec<ServerEntry>> {
+    let servers = match cm
+        .as_ref()
+        .and_then(|config_map| config_map.data.get("zoo.cfg"))
+    {
+        Some(zoo_cfg) => ZooCfg::parse(zoo_cfg)?.servers()?,
+        None => vec![],
+    };
 
-        if server_count == expected_server_count {
-            return Ok(());
-        }
+    if servers.len() == expected_server_count {
+        return Ok(servers);
     }
 
     Err(anyhow!(
         "ConfigMap server.<id> properties [{}] do not match the expected number of server.<id> properties [{}]",
-        server_count, expected_server_count
+        servers.len(), expected_server_count
     ))
 }
 
 /// Extracts the name of the `config_type_label` configmap of a pod.
-fn get_config_cm(client: &TestKubeClient, pod: &Pod, config_type_label: &str) -> Result<String> {
+fn get_config_cm(client: &dyn KubeApi, pod: &Pod, config_type_label: &str) -> Result<String> {
     let volumes = &pod.spec.as_ref().unwrap().volumes;
     let pod_name = pod.metadata.name.as_ref().unwrap();
 
@@ -186,7 +1233,7 @@ fn get_config_cm(client: &TestKubeClient, pod: &Pod, config_type_label: &str) ->
         {
             // get config map and check labels for `config_type_label` which indicates the type
             // of the config map we are looking for.
-            if let Some(config_map) = client.find_namespaced::<ConfigMap>(cm_name) {
+            if let Some(config_map) = client.find_config_map(cm_name) {
                 if config_map.metadata.labels.get(config_type_label)
                     == Some(&stackable_zookeeper_crd::CONFIG_MAP_TYPE_DATA.to_string())
                 {
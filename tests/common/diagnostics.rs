@@ -0,0 +1,204 @@
+use crate::common::kube_api::KubeApi;
+use crate::common::zookeeper::cluster_selector;
+use anyhow::{anyhow, Result};
+use integration_test_commons::operator::setup::TestCluster;
+use serde::Serialize;
+use stackable_zookeeper_crd::ZookeeperCluster;
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::env;
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+
+/// Directory below the cargo target directory where the diagnostics of failed tests are written.
+pub const DIAGNOSTICS_DIR: &str = "diagnostics";
+
+/// Name of the ZooKeeper container whose logs are collected.
+const ZOOKEEPER_CONTAINER: &str = "zookeeper";
+
+/// Number of four letter word and admin server responses which are kept for the diagnostics.
+const MAX_RECORDED_RESPONSES: usize = 50;
+
+thread_local! {
+    /// Cargo runs the tests of a file in parallel threads, so the responses are recorded per
+    /// thread to keep the responses of other tests out of the diagnostics. Responses to
+    /// requests of threads spawned by a test are not part of its diagnostics.
+    static RESPONSES: RefCell<VecDeque<String>> = const { RefCell::new(VecDeque::new()) };
+}
+
+/// Remember the response (or error) of a four letter word or admin server request of the
+/// current thread. Only the last MAX_RECORDED_RESPONSES are kept.
+pub fn record_response(request: &str, response: &Result<String>) {
+    let entry = match response {
+        Ok(body) => format!("==> {}\n{}\n", request, body),
+        Err(err) => format!("==> {}\nERROR: {:#}\n", request, err),
+    };
+
+    RESPONSES.with(|responses| {
+        let mut responses = responses.borrow_mut();
+        if responses.len() == MAX_RECORDED_RESPONSES {
+            responses.pop_front();
+        }
+        responses.push_back(entry);
+    });
+}
+
+/// The responses recorded by the current thread, oldest first.
+pub fn recorded_responses() -> Vec<String> {
+    RESPONSES.with(|responses| responses.borrow().iter().cloned().collect())
+}
+
+fn clear_recorded_responses() {
+    RESPONSES.with(|responses| responses.borrow_mut().clear());
+}
+
+/// The message of a caught panic.
+pub fn panic_message(panic: &(dyn Any + Send)) -> String {
+    panic
+        .downcast_ref::<String>()
+        .cloned()
+        .or_else(|| panic.downcast_ref::<&str>().map(|msg| msg.to_string()))
+        .unwrap_or_else(|| "<panic without message>".to_string())
+}
+
+/// Run a test against the ZooKeeper cluster with the given name. If the test returns an error
+/// or panics, the state of the cluster is written to `target/diagnostics/<test_name>` (see
+/// `dump_diagnostics`) before the error is returned or the panic is resumed.
+pub fn with_diagnostics<F>(
+    test_name: &str,
+    cluster: &mut TestCluster<ZookeeperCluster>,
+    cluster_name: &str,
+    test: F,
+) -> Result<()>
+where
+    F: FnOnce(&mut TestCluster<ZookeeperCluster>) -> Result<()>,
+{
+    clear_recorded_responses();
+
+    run_with_dump(cluster, test, |cluster, failure| {
+        let dir = diagnostics_dir(test_name);
+        dump_diagnostics(&dir, &cluster.client, cluster_name, failure).map_err(|err| {
+            anyhow!(
+                "Could not write diagnostics to [{}]: {:#}",
+                dir.display(),
+                err
+            )
+        })?;
+        println!("Wrote diagnostics to [{}]", dir.display());
+        Ok(())
+    })
+}
+
+/// Run the test and call dump with the error or panic message if it fails. The original
+/// failure is always returned or resumed: errors and panics of dump are only printed.
+pub fn run_with_dump<C, F, D>(context: &mut C, test: F, dump: D) -> Result<()>
+where
+    F: FnOnce(&mut C) -> Result<()>,
+    D: FnOnce(&C, &str) -> Result<()>,
+{
+    let result = panic::catch_unwind(AssertUnwindSafe(|| test(context)));
+
+    let failure = match &result {
+        Ok(Ok(())) => return Ok(()),
+        Ok(Err(err)) => format!("{:?}", err),
+        Err(panic) => panic_message(&**panic),
+    };
+
+    match panic::catch_unwind(AssertUnwindSafe(|| dump(context, &failure))) {
+        Ok(Ok(())) => {}
+        Ok(Err(err)) => println!("{:#}", err),
+        Err(panic) => println!(
+            "Writing the diagnostics panicked: {}",
+            panic_message(&*panic)
+        ),
+    }
+
+    match result {
+        Ok(result) => result,
+        Err(panic) => panic::resume_unwind(panic),
+    }
+}
+
+/// Write everything needed to debug a failed test into dir:
+/// - failure.txt: the error or panic message
+/// - cluster.yaml: the ZookeeperCluster custom resource including its status
+/// - pods.yaml, configmaps.yaml, services.yaml, endpoints.yaml and events.yaml: the resources
+///   belonging to the cluster
+/// - logs/<pod>.log: the logs of the ZooKeeper container of every pod
+/// - responses.txt: the last four letter word and admin server responses
+pub fn dump_diagnostics(
+    dir: &Path,
+    client: &dyn KubeApi,
+    cluster_name: &str,
+    failure: &str,
+) -> Result<()> {
+    if dir.exists() {
+        fs::remove_dir_all(dir)?;
+    }
+    fs::create_dir_all(dir.join("logs"))?;
+
+    fs::write(dir.join("failure.txt"), failure)?;
+    fs::write(dir.join("responses.txt"), recorded_responses().join("\n"))?;
+
+    let cluster = client.find_zookeeper_cluster(cluster_name);
+    let cluster_yaml = match &cluster {
+        Some(cluster) => serde_yaml::to_string(cluster)?,
+        None => format!("# ZookeeperCluster [{}] not found\n", cluster_name),
+    };
+    fs::write(dir.join("cluster.yaml"), cluster_yaml)?;
+
+    let selector = cluster_selector(cluster_name);
+    let pods = client.list_pods(&selector);
+    fs::write(dir.join("pods.yaml"), to_yaml(&pods)?)?;
+    fs::write(
+        dir.join("configmaps.yaml"),
+        to_yaml(&client.list_config_maps(&selector))?,
+    )?;
+    let services = client.list_services(&selector);
+    let endpoints: Vec<_> = services
+        .iter()
+        .filter_map(|service| client.find_endpoints(service.metadata.name.as_ref()?))
+        .collect();
+    fs::write(dir.join("services.yaml"), to_yaml(&services)?)?;
+    fs::write(dir.join("endpoints.yaml"), to_yaml(&endpoints)?)?;
+
+    let mut events = cluster
+        .as_ref()
+        .map(|cluster| client.get_cluster_events(cluster))
+        .unwrap_or_default();
+    for pod in &pods {
+        events.extend(client.get_events(pod));
+        let pod_name = pod
+            .metadata
+            .name
+            .as_ref()
+            .ok_or_else(|| anyhow!("Missing name in pod {:?}", pod))?;
+        fs::write(
+            dir.join("logs").join(format!("{}.log", pod_name)),
+            client.get_logs(pod, ZOOKEEPER_CONTAINER).join("\n"),
+        )?;
+    }
+    fs::write(dir.join("events.yaml"), to_yaml(&events)?)?;
+
+    Ok(())
+}
+
+/// The directory `<target dir>/diagnostics/<test_name>`. The target dir defaults to the
+/// "target" folder of the crate and can be changed via CARGO_TARGET_DIR.
+pub fn diagnostics_dir(test_name: &str) -> PathBuf {
+    let target_dir = env::var_os("CARGO_TARGET_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(env!("CARGO_MANIFEST_DIR")).join("target"));
+    target_dir.join(DIAGNOSTICS_DIR).join(test_name)
+}
+
+/// Serialize the resources as YAML documents separated by "---".
+fn to_yaml<T: Serialize>(resources: &[T]) -> Result<String> {
+    let mut documents = Vec::new();
+    for resource in resources {
+        documents.push(serde_yaml::to_string(resource)?);
+    }
+    Ok(documents.join(""))
+}
@@ -0,0 +1,137 @@
+use crate::common::checks::node_name;
+use crate::common::four_letter_commands::DEFAULT_CLIENT_PORT;
+use anyhow::{anyhow, Result};
+use integration_test_commons::test::prelude::Pod;
+use std::fmt;
+
+/// A parsed ZooKeeper connect string (format: host:port,host:port/chroot) as accepted by the
+/// native clients.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConnectString {
+    /// Host and port of every server in order of appearance. The port defaults to the client
+    /// port 2181.
+    pub hosts: Vec<(String, u16)>,
+    /// Path which is prepended to all paths of the client. A chroot of "/" is omitted.
+    pub chroot: Option<String>,
+}
+
+impl ConnectString {
+    pub fn parse(connect_string: &str) -> Result<ConnectString> {
+        let connect_string = connect_string.trim();
+        let (hosts, chroot) = match connect_string.find('/') {
+            Some(index) => (
+                &connect_string[..index],
+                parse_chroot(&connect_string[index..]).map_err(|err| {
+                    anyhow!("Invalid connect string [{}]: {}", connect_string, err)
+                })?,
+            ),
+            None => (connect_string, None),
+        };
+
+        let hosts = hosts
+            .split(',')
+            .map(|host| parse_host(host.trim()))
+            .collect::<Result<Vec<_>>>()
+            .map_err(|err| anyhow!("Invalid connect string [{}]: {}", connect_string, err))?;
+
+        Ok(ConnectString { hosts, chroot })
+    }
+
+    /// The addresses (format: <host>:<port>) of all servers.
+    pub fn addresses(&self) -> Vec<String> {
+        self.hosts
+            .iter()
+            .map(|(host, port)| format_address(host, *port))
+            .collect()
+    }
+}
+
+impl fmt::Display for ConnectString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}",
+            self.addresses().join(","),
+            self.chroot.as_deref().unwrap_or_default()
+        )
+    }
+}
+
+/// Build the connect string of the servers running in the given pods, i.e. the node of every
+/// pod on the client port, optionally followed by a chroot. It is not confirmed whether and
+/// where the operator publishes a connect string, so the tests derive it from the pods the same
+/// way they address the servers (see `node_address`).
+pub fn connect_string_for_pods(
+    pods: &[Pod],
+    client_port: u16,
+    chroot: Option<&str>,
+) -> Result<ConnectString> {
+    let hosts = pods
+        .iter()
+        .map(|pod| Ok(format_address(node_name(pod)?, client_port)))
+        .collect::<Result<Vec<_>>>()?;
+
+    ConnectString::parse(&format!(
+        "{}{}",
+        hosts.join(","),
+        chroot.unwrap_or_default()
+    ))
+}
+
+/// Parse "host", "host:port", "[ipv6]" or "[ipv6]:port".
+fn parse_host(host: &str) -> Result<(String, u16)> {
+    let (name, port) = if let Some(bracketed) = host.strip_prefix('[') {
+        let end = bracketed
+            .find(']')
+            .ok_or_else(|| anyhow!("Unclosed bracket in host [{}]", host))?;
+        let port = match &bracketed[end + 1..] {
+            "" => None,
+            rest => Some(
+                rest.strip_prefix(':')
+                    .ok_or_else(|| anyhow!("Unexpected characters after host [{}]", host))?,
+            ),
+        };
+        (&bracketed[..end], port)
+    } else {
+        match host.split_once(':') {
+            Some((_, port)) if port.contains(':') => {
+                return Err(anyhow!("IPv6 host [{}] must be enclosed in brackets", host))
+            }
+            Some((name, port)) => (name, Some(port)),
+            None => (host, None),
+        }
+    };
+
+    if name.is_empty() {
+        return Err(anyhow!("Empty host in [{}]", host));
+    }
+
+    let port = match port {
+        Some(port) => match port.parse::<u16>() {
+            Ok(port) if port > 0 => port,
+            _ => return Err(anyhow!("Invalid port [{}] of host [{}]", port, host)),
+        },
+        None => DEFAULT_CLIENT_PORT,
+    };
+
+    Ok((name.to_string(), port))
+}
+
+/// A chroot must be a valid absolute path. "/" is equivalent to no chroot.
+fn parse_chroot(chroot: &str) -> Result<Option<String>> {
+    if chroot == "/" {
+        return Ok(None);
+    }
+    if chroot.ends_with('/') || chroot.contains("//") {
+        return Err(anyhow!("Chroot [{}] is no valid path", chroot));
+    }
+    Ok(Some(chroot.to_string()))
+}
+
+fn format_address(host: &str, port: u16) -> String {
+    if host.contains(':') {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    }
+}
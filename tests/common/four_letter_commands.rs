@@ -1,145 +1,424 @@
+use crate::common::admin_server::{
+    AdminClient, AdminServerOptions, AdminServerResponse, Conf, DEFAULT_ADMIN_PORT,
+};
+use crate::common::capabilities::{Protocol, ZkCapabilities};
+use crate::common::diagnostics::record_response;
+use crate::common::retry::RetryPolicy;
 use anyhow::{anyhow, Result};
 use integration_test_commons::test::prelude::Pod;
-use semver::Version;
 use serde::{Deserialize, Serialize};
 use stackable_zookeeper_crd::ZookeeperVersion;
+use std::collections::BTreeMap;
+use std::fmt;
 use std::io::{Read, Write};
-use std::net::TcpStream;
-use std::thread;
-use std::time::{Duration, Instant};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
 
 /// If pods are set to "ready" the cluster may still need time to balance to be fully ready.
-/// Therefore we resend the 4lw (if not successful) in the defined timeout period.
+/// Therefore we resend the 4lw (if not successful) in the defined timeout period (in seconds,
+/// see `RetryPolicy` to stretch it).
 const FOUR_LETTER_WORD_REQUEST_TIMEOUT: u64 = 10;
 
+/// Timeout in seconds for connecting to, writing to and reading from a ZooKeeper server when
+/// sending a raw four letter word. The server closes the connection after the response has
+/// been written, so a read timeout only occurs if the server hangs.
+const FOUR_LETTER_WORD_SOCKET_TIMEOUT: u64 = 5;
+
+/// Upper bound for the size of a raw four letter word response. Commands like "dump" or "cons"
+/// grow with the number of sessions, which should never come close to this in our tests.
+const FOUR_LETTER_WORD_MAX_RESPONSE_BYTES: u64 = 1024 * 1024;
+
+/// Print details about the serving configuration.
+pub const CONFIGURATION: &str = "conf";
+/// List full connection/session details for all clients connected to this server.
+pub const CONNECTIONS: &str = "cons";
 /// Lists the outstanding sessions and ephemeral nodes. This only works on the leader.
-//pub const DUMP: &str = "dump";
+pub const DUMP: &str = "dump";
 /// Print details about serving environment
-//pub const ENVIRONMENT: &str = "envi";
+pub const ENVIRONMENT: &str = "envi";
 /// Shuts down the server. This must be issued from the machine the ZooKeeper server is running on.
-//pub const KILL_SERVER: &str = "kill";
+pub const KILL_SERVER: &str = "kill";
 /// List outstanding requests
-//pub const LIST_REQUESTS: &str = "reqs";
+pub const LIST_REQUESTS: &str = "reqs";
+/// Outputs a list of variables that could be used for monitoring the health of the cluster.
+pub const MONITOR: &str = "mntr";
 /// Tests if server is running in a non-error state. The server will respond with imok if it is running.
 /// Otherwise it will not respond at all.
 pub const ARE_YOU_OK: &str = "ruok";
 /// Reset statistics returned by stat command.
-//pub const RESET_STATISTICS: &str = "srst";
+pub const RESET_STATISTICS: &str = "srst";
+/// Lists full details for the server.
+pub const SERVER_DETAILS: &str = "srvr";
 /// Lists statistics about performance and connected clients.
-//pub const LIST_STATISTICS: &str = "stat";
+pub const LIST_STATISTICS: &str = "stat";
+/// Lists brief information on watches for the server.
+pub const WATCHES: &str = "wchs";
 
 /// Positive response for the "ruok" command.
 pub const I_AM_OK: &str = "imok";
 
+/// Default port for client connections and raw four letter words.
+pub const DEFAULT_CLIENT_PORT: u16 = 2181;
+
+/// How the checks send commands to the servers of a cluster. The protocol is taken from the
+/// `ZkCapabilities` of the version: raw four letter words are sent to the client port, while
+/// admin server commands use the scheme, port and TLS settings of the admin server options.
+#[derive(Clone, Debug)]
+pub struct ServerAccess {
+    pub version: ZookeeperVersion,
+    pub client_port: u16,
+    pub admin_server: AdminServerOptions,
+}
+
+impl ServerAccess {
+    /// Access via the default client port and a plain http admin server on the default admin
+    /// port of the version.
+    pub fn new(version: &ZookeeperVersion) -> Result<Self> {
+        let capabilities = ZkCapabilities::for_version(version)?;
+        Ok(ServerAccess {
+            version: version.clone(),
+            client_port: DEFAULT_CLIENT_PORT,
+            admin_server: AdminServerOptions {
+                port: capabilities.admin_port.unwrap_or(DEFAULT_ADMIN_PORT),
+                ..AdminServerOptions::default()
+            },
+        })
+    }
+
+    pub fn client_port(mut self, client_port: u16) -> Self {
+        self.client_port = client_port;
+        self
+    }
+
+    /// Scheme, port and TLS settings of the admin server, e.g. for clusters serving it via https.
+    pub fn admin_server(mut self, admin_server: AdminServerOptions) -> Self {
+        self.admin_server = admin_server;
+        self
+    }
+
+    pub fn capabilities(&self) -> Result<ZkCapabilities> {
+        ZkCapabilities::for_version(&self.version)
+    }
+}
+
 /// Send "ruok" to a pod and check if the response is "imok"
-pub fn send_4lw_i_am_ok(pod: &Pod, version: &ZookeeperVersion, port: u16) -> Result<()> {
-    // 3.4.14 answers with "imok", while 3.5.X onwards the command is mirrored
-    // which results in the "ruok" response we have to differentiate here.
-    let ver = if Version::parse(&version.to_string())? > Version::parse("3.5.2")? {
-        ARE_YOU_OK.to_string()
-    } else {
-        I_AM_OK.to_string()
-    };
+pub fn send_4lw_i_am_ok(pod: &Pod, access: &ServerAccess) -> Result<()> {
+    let expected = access.capabilities()?.ruok_reply;
+    let host_name = pod.spec.as_ref().unwrap().node_name.as_ref().unwrap();
 
     // The cluster requires some time to balance after all pods are "ready". This may result in four
-    // letter word requests to fail shortly after. He we resend the four letter word every two
-    // seconds for the defined timeout FOUR_LETTER_WORD_REQUEST_TIMEOUT.
-    let now = Instant::now();
-    let mut last_response = Ok("<no-response-received>".to_string());
-
-    while now.elapsed().as_secs() < Duration::from_secs(FOUR_LETTER_WORD_REQUEST_TIMEOUT).as_secs()
-    {
-        last_response = send_4lw(
-            version,
-            ARE_YOU_OK,
-            &format!(
-                "{}:{}",
-                pod.spec.as_ref().unwrap().node_name.as_ref().unwrap(),
-                port
-            ),
-        );
-
-        if last_response.is_err() || last_response.as_ref().unwrap() != &ver {
-            println!(
-                "[{}] Received: {:?}. Will resend command.",
-                ARE_YOU_OK, last_response
+    // letter word requests to fail shortly after. Here we resend the four letter word until the
+    // timeout FOUR_LETTER_WORD_REQUEST_TIMEOUT is reached.
+    RetryPolicy::new(Duration::from_secs(FOUR_LETTER_WORD_REQUEST_TIMEOUT)).retry(
+        &format!("{} {}", ARE_YOU_OK, host_name),
+        || {
+            let response = send_4lw(access, ARE_YOU_OK, host_name)?;
+            if response == expected {
+                Ok(())
+            } else {
+                Err(anyhow!("Received [{}] instead of [{}]", response, expected))
+            }
+        },
+    )
+}
+
+/// This sends the "four letter word" in order to check if the cluster is ready or to get
+/// statistics. The protocol (raw four letter word or admin server) is taken from the
+/// `ZkCapabilities` of the version.
+pub fn send_4lw(access: &ServerAccess, four_letter_word: &str, host_name: &str) -> Result<String> {
+    match access.capabilities()?.protocol {
+        Protocol::AdminServer => {
+            send_cmd_to_admin_server(four_letter_word, host_name, &access.admin_server)
+        }
+        Protocol::FourLetterWord => send_4lw_to_host(
+            four_letter_word,
+            &format!("{}:{}", host_name, access.client_port),
+        ),
+    }
+}
+
+/// Request the "mntr" statistics of the server running on host_name.
+pub fn send_mntr(access: &ServerAccess, host_name: &str) -> Result<Mntr> {
+    match access.capabilities()?.protocol {
+        Protocol::AdminServer => {
+            AdminClient::with_options(host_name, &access.admin_server)?.command::<Mntr>()
+        }
+        Protocol::FourLetterWord => Mntr::from_4lw_response(&send_4lw_to_host(
+            MONITOR,
+            &format!("{}:{}", host_name, access.client_port),
+        )?),
+    }
+}
+
+/// Request the id (myid) of the server running on host_name via "conf".
+pub fn get_server_id(access: &ServerAccess, host_name: &str) -> Result<u64> {
+    let server_id = match access.capabilities()?.protocol {
+        Protocol::AdminServer => {
+            AdminClient::with_options(host_name, &access.admin_server)?
+                .command::<Conf>()?
+                .server_id
+        }
+        // the raw response contains one "key=value" pair per line
+        Protocol::FourLetterWord => send_4lw_to_host(
+            CONFIGURATION,
+            &format!("{}:{}", host_name, access.client_port),
+        )?
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("serverId="))
+        .map(|server_id| server_id.trim().parse())
+        .transpose()?,
+    };
+
+    server_id.ok_or_else(|| anyhow!("Server [{}] did not report its server id", host_name))
+}
+
+/// The state a ZooKeeper server reports about itself via "mntr" or "srvr".
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ServerState {
+    Leader,
+    Follower,
+    Observer,
+    Standalone,
+    ReadOnly,
+}
+
+impl ServerState {
+    pub fn parse(state: &str) -> Result<ServerState> {
+        match state.trim() {
+            "leader" => Ok(ServerState::Leader),
+            "follower" => Ok(ServerState::Follower),
+            "observer" => Ok(ServerState::Observer),
+            "standalone" => Ok(ServerState::Standalone),
+            "read-only" => Ok(ServerState::ReadOnly),
+            other => Err(anyhow!("Unknown ZooKeeper server state [{}]", other)),
+        }
+    }
+}
+
+impl fmt::Display for ServerState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = match self {
+            ServerState::Leader => "leader",
+            ServerState::Follower => "follower",
+            ServerState::Observer => "observer",
+            ServerState::Standalone => "standalone",
+            ServerState::ReadOnly => "read-only",
+        };
+        write!(f, "{}", state)
+    }
+}
+
+/// Typed output of the "mntr" command. Version 3.4.x returns tab separated "zk_<key>\t<value>"
+/// lines via the raw four letter word, while the admin server of 3.5.x returns a JSON object
+/// with the same keys without the "zk_" prefix. Both are parsed into this struct.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mntr {
+    pub version: String,
+    pub server_state: ServerState,
+    pub avg_latency: f64,
+    pub max_latency: i64,
+    pub min_latency: i64,
+    pub packets_received: u64,
+    pub packets_sent: u64,
+    pub num_alive_connections: u64,
+    pub outstanding_requests: u64,
+    pub znode_count: u64,
+    pub watch_count: u64,
+    pub ephemerals_count: u64,
+    pub approximate_data_size: u64,
+    /// Only available on unix systems.
+    pub open_file_descriptor_count: Option<u64>,
+    /// Only available on unix systems.
+    pub max_file_descriptor_count: Option<u64>,
+    /// Only reported by the leader.
+    pub followers: Option<u64>,
+    /// Only reported by the leader.
+    pub synced_followers: Option<u64>,
+    /// Only reported by the leader.
+    pub pending_syncs: Option<u64>,
+    /// All reported key value pairs (without "zk_" prefix), including those not mapped above.
+    pub values: BTreeMap<String, String>,
+}
+
+impl Mntr {
+    /// Parse the tab separated response of the raw "mntr" four letter word.
+    pub fn from_4lw_response(response: &str) -> Result<Mntr> {
+        let mut values = BTreeMap::new();
+
+        for line in response.lines().filter(|line| !line.trim().is_empty()) {
+            let (key, value) = match line.split_once('\t') {
+                Some(key_value) => key_value,
+                None => return Err(anyhow!("Malformed mntr line [{}]", line)),
+            };
+            let key = key.trim();
+            values.insert(
+                key.strip_prefix("zk_").unwrap_or(key).to_string(),
+                value.trim().to_string(),
             );
-            thread::sleep(Duration::from_secs(2));
-            continue;
         }
 
-        return Ok(());
+        Mntr::from_values(values)
     }
 
-    Err(anyhow!(
-        "Could not verify cluster status response via [{}] within the specified timeout [{}]: {:?}",
-        ARE_YOU_OK,
-        FOUR_LETTER_WORD_REQUEST_TIMEOUT,
-        last_response
-    ))
+    /// Parse the JSON response of the admin server "monitor" command.
+    pub fn from_admin_server_response(response: &str) -> Result<Mntr> {
+        let json: serde_json::Value = serde_json::from_str(response)?;
+        let object = json
+            .as_object()
+            .ok_or_else(|| anyhow!("Admin server monitor response is not a JSON object"))?;
+
+        if let Some(error) = object.get("error").and_then(|error| error.as_str()) {
+            return Err(anyhow!(
+                "Received error while executing command to admin server: {}",
+                error
+            ));
+        }
+
+        let mut values = BTreeMap::new();
+        for (key, value) in object {
+            let value = match value {
+                serde_json::Value::Null => continue,
+                serde_json::Value::String(value) => value.clone(),
+                other => other.to_string(),
+            };
+            values.insert(key.clone(), value);
+        }
+
+        Mntr::from_values(values)
+    }
+
+    fn from_values(values: BTreeMap<String, String>) -> Result<Mntr> {
+        Ok(Mntr {
+            version: required(&values, "version")?.to_string(),
+            server_state: ServerState::parse(required(&values, "server_state")?)?,
+            avg_latency: parse_required(&values, "avg_latency")?,
+            max_latency: parse_required(&values, "max_latency")?,
+            min_latency: parse_required(&values, "min_latency")?,
+            packets_received: parse_required(&values, "packets_received")?,
+            packets_sent: parse_required(&values, "packets_sent")?,
+            num_alive_connections: parse_required(&values, "num_alive_connections")?,
+            outstanding_requests: parse_required(&values, "outstanding_requests")?,
+            znode_count: parse_required(&values, "znode_count")?,
+            watch_count: parse_required(&values, "watch_count")?,
+            ephemerals_count: parse_required(&values, "ephemerals_count")?,
+            approximate_data_size: parse_required(&values, "approximate_data_size")?,
+            open_file_descriptor_count: parse_optional(&values, "open_file_descriptor_count")?,
+            max_file_descriptor_count: parse_optional(&values, "max_file_descriptor_count")?,
+            // later versions renamed "followers" to "learners"
+            followers: match parse_optional(&values, "followers")? {
+                Some(followers) => Some(followers),
+                None => parse_optional(&values, "learners")?,
+            },
+            synced_followers: parse_optional(&values, "synced_followers")?,
+            pending_syncs: parse_optional(&values, "pending_syncs")?,
+            values,
+        })
+    }
 }
 
-/// This sends the "four letter word" in order to check if the cluster is ready or to get
-/// statistics. We have to differentiate between the ZooKeeper versions.
-/// Up to 3.5.2 the standard four letter word can be used.
-/// From 3.5.3 onwards we query the admin server via http request.
-pub fn send_4lw(version: &ZookeeperVersion, four_letter_word: &str, host: &str) -> Result<String> {
-    if Version::parse(&version.to_string())? > Version::parse("3.5.2")? {
-        send_cmd_to_admin_server(four_letter_word, host)
-    } else {
-        send_4lw_to_host(four_letter_word, host)
+fn required<'a>(values: &'a BTreeMap<String, String>, key: &str) -> Result<&'a str> {
+    values
+        .get(key)
+        .map(String::as_str)
+        .ok_or_else(|| anyhow!("Missing [{}] in mntr response", key))
+}
+
+fn parse_required<T: std::str::FromStr>(values: &BTreeMap<String, String>, key: &str) -> Result<T> {
+    let value = required(values, key)?;
+    value.parse().map_err(|_| {
+        anyhow!(
+            "Could not parse [{}] value [{}] in mntr response",
+            key,
+            value
+        )
+    })
+}
+
+fn parse_optional<T: std::str::FromStr>(
+    values: &BTreeMap<String, String>,
+    key: &str,
+) -> Result<Option<T>> {
+    match values.get(key) {
+        None => Ok(None),
+        Some(_) => parse_required(values, key).map(Some),
     }
 }
 
 /// Create a TCP connection to the given host name (format: <host>:<port>) and send the
 /// provided 4 letter command (e.g. "ruok") and return the received response.
-/// The response is hardcoded to be 4 letters as well, even though some of the four
-/// letter commands of ZooKeeper return more data (e.g. stat).
+/// The server closes the connection once the response is written, so we read until EOF. This
+/// returns the full payload for commands with longer responses (e.g. "stat" or "mntr"). Reads
+/// time out after FOUR_LETTER_WORD_SOCKET_TIMEOUT seconds and responses larger than
+/// FOUR_LETTER_WORD_MAX_RESPONSE_BYTES are rejected.
 /// This only works until version 3.5.2. With version 3.5.3 this functionality was moved to the
 /// admin server. To keep up the four letter words you have to whitelist the required commands
 /// in the zoo.cfg via: "4lw.commands.whitelist=*" ("*" for all commands to be whitelisted)
 fn send_4lw_to_host(four_letter_word: &str, host: &str) -> Result<String> {
-    let mut stream = TcpStream::connect(host)?;
+    let response = request_4lw(four_letter_word, host);
+    record_response(&format!("{} {}", four_letter_word, host), &response);
+    response
+}
+
+fn request_4lw(four_letter_word: &str, host: &str) -> Result<String> {
+    let timeout = Duration::from_secs(FOUR_LETTER_WORD_SOCKET_TIMEOUT);
+    let address = host
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| anyhow!("Could not resolve any address for host [{}]", host))?;
+
+    let mut stream = TcpStream::connect_timeout(&address, timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
 
     println!("Writing [{}] to [{}]", four_letter_word, host);
     stream.write_all(four_letter_word.as_bytes())?;
     stream.flush()?;
 
-    let mut response = [0u8; 4];
-    stream.read_exact(&mut response)?;
+    // read one byte more than allowed to detect oversized responses
+    let mut response = Vec::new();
+    (&mut stream)
+        .take(FOUR_LETTER_WORD_MAX_RESPONSE_BYTES + 1)
+        .read_to_end(&mut response)
+        .map_err(|err| {
+            anyhow!(
+                "Error while reading response for [{}] from [{}]: {}",
+                four_letter_word,
+                host,
+                err
+            )
+        })?;
 
-    let received = std::str::from_utf8(&response).expect("valid utf8");
+    if response.len() as u64 > FOUR_LETTER_WORD_MAX_RESPONSE_BYTES {
+        return Err(anyhow!(
+            "Response for [{}] from [{}] exceeds the maximum size of [{}] bytes",
+            four_letter_word,
+            host,
+            FOUR_LETTER_WORD_MAX_RESPONSE_BYTES
+        ));
+    }
 
-    println!("Received: {}", received);
-    Ok(received.to_string())
-}
+    let received = String::from_utf8(response)?;
 
-#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
-struct AdminServerResponse {
-    pub command: String,
-    pub error: Option<String>,
+    println!("Received: {}", received);
+    Ok(received)
 }
 
-/// Send a http request to "http://HOST:PORT/commands/COMMAND"
+/// Send a http request to "SCHEME://HOST:PORT/commands/COMMAND"
 /// It will return a JSON response containing at least:
 /// {
 ///     command: "some_string",
 ///     error: "some_error"
 /// }
 /// If no errors occur, "null" (which in serde parses to None) is returned
-fn send_cmd_to_admin_server(command: &str, host: &str) -> Result<String> {
-    // TODO: Support https
-    let url = format!("http://{}/commands/{}", host, command);
-
-    println!("Requesting [{}]", url);
-    let mut res = reqwest::blocking::get(&url)?;
-    let mut body = String::new();
-    res.read_to_string(&mut body)?;
-
+fn send_cmd_to_admin_server(
+    command: &str,
+    host_name: &str,
+    options: &AdminServerOptions,
+) -> Result<String> {
+    let body = AdminClient::with_options(host_name, options)?.request(command)?;
     let response: AdminServerResponse = serde_json::from_str(&body)?;
 
-    println!("Received: {}", body);
-
     if response.error.is_none() {
         return Ok(response.command);
     }
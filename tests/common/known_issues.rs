@@ -0,0 +1,88 @@
+use crate::common::checks::CONFIG_MAP_CHECK_FAILED;
+use crate::common::diagnostics::panic_message;
+use anyhow::{anyhow, Result};
+use std::env;
+use std::panic::{self, AssertUnwindSafe};
+
+/// If set to "true", tests expected to fail due to a known issue are run like any other test,
+/// e.g. to check whether a fix of the issue works.
+pub const ENV_IGNORE_KNOWN_ISSUES: &str = "ZK_TEST_IGNORE_KNOWN_ISSUES";
+
+/// A bug (usually in the operator) which makes a test fail until it is fixed. Tests expecting
+/// the issue with `expect_failure` fail as soon as they pass, so the expectation has to be
+/// removed together with the fix.
+#[derive(Clone, Copy, Debug)]
+pub struct KnownIssue {
+    /// The filed issue in the tracker of the affected project.
+    pub url: &'static str,
+    pub summary: &'static str,
+    /// Part of the failure message which shows that a test failed due to this issue. Failures
+    /// without it are not covered by the issue.
+    pub symptom: &'static str,
+}
+
+/// Config maps are not updated correctly when the cluster is scaled.
+pub const CONFIG_MAPS_NOT_UPDATED_ON_SCALING: KnownIssue = KnownIssue {
+    url: "https://github.com/stackabletech/zookeeper-operator/issues/128",
+    summary: "config maps are not updated correctly when the cluster is scaled",
+    symptom: CONFIG_MAP_CHECK_FAILED,
+};
+
+/// Runs a test which is expected to fail due to a known issue. Instead of being skipped, the
+/// test runs and its outcome is reported: a failure (error or panic) showing the symptom of
+/// the issue is printed together with the issue and accepted. Other failures are returned, and
+/// an unexpected success fails, so the expectation is removed once the issue is fixed. With
+/// ENV_IGNORE_KNOWN_ISSUES set, the test result is returned as is.
+pub fn expect_failure<F>(issue: &KnownIssue, test: F) -> Result<()>
+where
+    F: FnOnce() -> Result<()>,
+{
+    if ignore_known_issues()? {
+        println!(
+            "Ignoring known issue [{}] because [{}] is set",
+            issue.url, ENV_IGNORE_KNOWN_ISSUES
+        );
+        return test();
+    }
+
+    let failure = match panic::catch_unwind(AssertUnwindSafe(test)) {
+        Ok(Ok(())) => {
+            return Err(anyhow!(
+                "Test passed although it is expected to fail due to [{}] ({}). Remove the expected failure if the issue is fixed.",
+                issue.url,
+                issue.summary
+            ))
+        }
+        Ok(Err(err)) => format!("{:?}", err),
+        Err(panic) => panic_message(&*panic),
+    };
+
+    if !failure.contains(issue.symptom) {
+        return Err(anyhow!(
+            "Test failed without the symptom [{}] of the known issue [{}] ({}): {}",
+            issue.symptom,
+            issue.url,
+            issue.summary,
+            failure
+        ));
+    }
+
+    println!(
+        "Test failed as expected due to [{}] ({}): {}",
+        issue.url, issue.summary, failure
+    );
+    Ok(())
+}
+
+fn ignore_known_issues() -> Result<bool> {
+    match env::var(ENV_IGNORE_KNOWN_ISSUES) {
+        Ok(value) => value.trim().parse().map_err(|_| {
+            anyhow!(
+                "Invalid value [{}] for environment variable [{}]",
+                value,
+                ENV_IGNORE_KNOWN_ISSUES
+            )
+        }),
+        Err(_) => Ok(false),
+    }
+}
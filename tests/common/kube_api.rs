@@ -0,0 +1,271 @@
+use anyhow::{anyhow, Result};
+use integration_test_commons::test::kube::{LogParams, TestKubeClient};
+use integration_test_commons::test::prelude::{ConfigMap, Pod};
+use k8s_openapi::api::core::v1::{Endpoints, Event, Node, Service};
+use serde::Deserialize;
+use stackable_zookeeper_crd::ZookeeperCluster;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Directory (relative to the crate root) containing recorded Kubernetes manifests.
+pub const FIXTURES_DIR: &str = "tests/fixtures";
+
+/// The Kubernetes operations required by the checks and diagnostics. Implemented by the
+/// `TestKubeClient` for tests against a cluster and by the `FakeKubeApi` for offline tests.
+pub trait KubeApi {
+    fn find_zookeeper_cluster(&self, name: &str) -> Option<ZookeeperCluster>;
+
+    /// Find a configmap in the test namespace.
+    fn find_config_map(&self, name: &str) -> Option<ConfigMap>;
+
+    fn find_node(&self, name: &str) -> Option<Node>;
+
+    /// List the pods matching the label selector (format: key1=value1,key2=value2).
+    fn list_pods(&self, label_selector: &str) -> Vec<Pod>;
+
+    /// List the configmaps matching the label selector (format: key1=value1,key2=value2).
+    fn list_config_maps(&self, label_selector: &str) -> Vec<ConfigMap>;
+
+    /// List the services matching the label selector (format: key1=value1,key2=value2).
+    fn list_services(&self, label_selector: &str) -> Vec<Service>;
+
+    /// Find the endpoints of a service in the test namespace.
+    fn find_endpoints(&self, name: &str) -> Option<Endpoints>;
+
+    /// Get the events regarding the pod.
+    fn get_events(&self, pod: &Pod) -> Vec<Event>;
+
+    /// Get the events regarding the ZooKeeper custom resource.
+    fn get_cluster_events(&self, cluster: &ZookeeperCluster) -> Vec<Event>;
+
+    /// Get the log lines of a container of the pod.
+    fn get_logs(&self, pod: &Pod, container: &str) -> Vec<String>;
+}
+
+impl KubeApi for TestKubeClient {
+    fn find_zookeeper_cluster(&self, name: &str) -> Option<ZookeeperCluster> {
+        self.find_namespaced(name)
+    }
+
+    fn find_config_map(&self, name: &str) -> Option<ConfigMap> {
+        self.find_namespaced(name)
+    }
+
+    fn find_node(&self, name: &str) -> Option<Node> {
+        self.find(name)
+    }
+
+    fn list_pods(&self, label_selector: &str) -> Vec<Pod> {
+        self.list_labeled(label_selector).items
+    }
+
+    fn list_config_maps(&self, label_selector: &str) -> Vec<ConfigMap> {
+        self.list_labeled(label_selector).items
+    }
+
+    fn list_services(&self, label_selector: &str) -> Vec<Service> {
+        self.list_labeled(label_selector).items
+    }
+
+    fn find_endpoints(&self, name: &str) -> Option<Endpoints> {
+        self.find_namespaced(name)
+    }
+
+    fn get_events(&self, pod: &Pod) -> Vec<Event> {
+        TestKubeClient::get_events(self, pod)
+    }
+
+    fn get_cluster_events(&self, cluster: &ZookeeperCluster) -> Vec<Event> {
+        TestKubeClient::get_events(self, cluster)
+    }
+
+    fn get_logs(&self, pod: &Pod, container: &str) -> Vec<String> {
+        let params = LogParams {
+            container: Some(container.to_string()),
+            ..LogParams::default()
+        };
+        TestKubeClient::get_logs(self, pod, &params)
+    }
+}
+
+/// In-memory stand-in for the Kubernetes API serving resources loaded from YAML manifests.
+#[derive(Clone, Debug, Default)]
+pub struct FakeKubeApi {
+    clusters: BTreeMap<String, ZookeeperCluster>,
+    config_maps: BTreeMap<String, ConfigMap>,
+    nodes: BTreeMap<String, Node>,
+    pods: BTreeMap<String, Pod>,
+    services: BTreeMap<String, Service>,
+    endpoints: BTreeMap<String, Endpoints>,
+    events: Vec<Event>,
+    /// Log lines per pod and container name.
+    logs: BTreeMap<(String, String), Vec<String>>,
+}
+
+impl FakeKubeApi {
+    /// Load the manifests of `FIXTURES_DIR/<name>.yaml`.
+    pub fn from_fixture(name: &str) -> Result<Self> {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join(FIXTURES_DIR)
+            .join(format!("{}.yaml", name));
+        let content = fs::read_to_string(&path)
+            .map_err(|err| anyhow!("Could not read fixture [{}]: {}", path.display(), err))?;
+        Self::from_yaml(&content)
+    }
+
+    /// Load resources from YAML manifests separated by "---".
+    pub fn from_yaml(content: &str) -> Result<Self> {
+        let mut api = FakeKubeApi::default();
+        api.apply_yaml(content)?;
+        Ok(api)
+    }
+
+    /// Add resources from YAML manifests separated by "---". Resources with the same kind and
+    /// name are replaced. Supported kinds are ConfigMap, Endpoints, Event, Node, Pod, Service
+    /// and ZookeeperCluster.
+    pub fn apply_yaml(&mut self, content: &str) -> Result<()> {
+        for document in serde_yaml::Deserializer::from_str(content) {
+            let value = serde_yaml::Value::deserialize(document)?;
+            if value.is_null() {
+                continue;
+            }
+
+            let kind = value
+                .get("kind")
+                .and_then(serde_yaml::Value::as_str)
+                .ok_or_else(|| anyhow!("Missing kind in manifest: {:?}", value))?
+                .to_string();
+
+            match kind.as_str() {
+                "ConfigMap" => {
+                    let config_map: ConfigMap = serde_yaml::from_value(value)?;
+                    self.config_maps
+                        .insert(resource_name(&config_map.metadata.name)?, config_map);
+                }
+                "Endpoints" => {
+                    let endpoints: Endpoints = serde_yaml::from_value(value)?;
+                    self.endpoints
+                        .insert(resource_name(&endpoints.metadata.name)?, endpoints);
+                }
+                "Event" => self.events.push(serde_yaml::from_value(value)?),
+                "Node" => {
+                    let node: Node = serde_yaml::from_value(value)?;
+                    self.nodes.insert(resource_name(&node.metadata.name)?, node);
+                }
+                "Pod" => {
+                    let pod: Pod = serde_yaml::from_value(value)?;
+                    self.pods.insert(resource_name(&pod.metadata.name)?, pod);
+                }
+                "Service" => {
+                    let service: Service = serde_yaml::from_value(value)?;
+                    self.services
+                        .insert(resource_name(&service.metadata.name)?, service);
+                }
+                "ZookeeperCluster" => {
+                    let cluster: ZookeeperCluster = serde_yaml::from_value(value)?;
+                    self.clusters
+                        .insert(resource_name(&cluster.metadata.name)?, cluster);
+                }
+                other => return Err(anyhow!("Unsupported kind [{}] in manifest", other)),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Set the log lines returned for a container of a pod.
+    pub fn set_logs(&mut self, pod_name: &str, container: &str, lines: &[&str]) {
+        self.logs.insert(
+            (pod_name.to_string(), container.to_string()),
+            lines.iter().map(|line| line.to_string()).collect(),
+        );
+    }
+
+    fn events_regarding(&self, kind: &str, name: &Option<String>) -> Vec<Event> {
+        self.events
+            .iter()
+            .filter(|event| {
+                event.involved_object.kind.as_deref() == Some(kind)
+                    && &event.involved_object.name == name
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+impl KubeApi for FakeKubeApi {
+    fn find_zookeeper_cluster(&self, name: &str) -> Option<ZookeeperCluster> {
+        self.clusters.get(name).cloned()
+    }
+
+    fn find_config_map(&self, name: &str) -> Option<ConfigMap> {
+        self.config_maps.get(name).cloned()
+    }
+
+    fn find_node(&self, name: &str) -> Option<Node> {
+        self.nodes.get(name).cloned()
+    }
+
+    fn list_pods(&self, label_selector: &str) -> Vec<Pod> {
+        self.pods
+            .values()
+            .filter(|pod| matches_labels(&pod.metadata.labels, label_selector))
+            .cloned()
+            .collect()
+    }
+
+    fn list_config_maps(&self, label_selector: &str) -> Vec<ConfigMap> {
+        self.config_maps
+            .values()
+            .filter(|config_map| matches_labels(&config_map.metadata.labels, label_selector))
+            .cloned()
+            .collect()
+    }
+
+    fn list_services(&self, label_selector: &str) -> Vec<Service> {
+        self.services
+            .values()
+            .filter(|service| matches_labels(&service.metadata.labels, label_selector))
+            .cloned()
+            .collect()
+    }
+
+    fn find_endpoints(&self, name: &str) -> Option<Endpoints> {
+        self.endpoints.get(name).cloned()
+    }
+
+    fn get_events(&self, pod: &Pod) -> Vec<Event> {
+        self.events_regarding("Pod", &pod.metadata.name)
+    }
+
+    fn get_cluster_events(&self, cluster: &ZookeeperCluster) -> Vec<Event> {
+        self.events_regarding("ZookeeperCluster", &cluster.metadata.name)
+    }
+
+    fn get_logs(&self, pod: &Pod, container: &str) -> Vec<String> {
+        pod.metadata
+            .name
+            .clone()
+            .and_then(|pod_name| self.logs.get(&(pod_name, container.to_string())))
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+fn resource_name(name: &Option<String>) -> Result<String> {
+    name.clone()
+        .ok_or_else(|| anyhow!("Missing metadata.name in manifest"))
+}
+
+/// Equality based label selectors only (e.g. "app.kubernetes.io/name=zookeeper,node=1").
+fn matches_labels(labels: &BTreeMap<String, String>, label_selector: &str) -> bool {
+    label_selector
+        .split(',')
+        .map(str::trim)
+        .filter(|requirement| !requirement.is_empty())
+        .all(|requirement| match requirement.split_once('=') {
+            Some((key, value)) => labels.get(key.trim()).map(String::as_str) == Some(value.trim()),
+            None => labels.contains_key(requirement),
+        })
+}
@@ -0,0 +1,141 @@
+use crate::common::capabilities::MetricsFormat;
+use crate::common::diagnostics::record_response;
+use crate::common::four_letter_commands::ServerState;
+use crate::common::prometheus::{Metrics, Sample};
+use anyhow::{anyhow, Result};
+use std::time::Duration;
+
+/// Timeout in seconds for a single request to the metrics endpoint.
+const METRICS_REQUEST_TIMEOUT: u64 = 10;
+
+/// Metrics of the ZooKeeper MBeans as named by the JMX exporter. Servers of an ensemble report
+/// them with the labels "replicaId" and "memberType", standalone servers without labels.
+pub const JMX_ALIVE_CONNECTIONS: &str = "zookeeper_NumAliveConnections";
+pub const JMX_OUTSTANDING_REQUESTS: &str = "zookeeper_OutstandingRequests";
+/// Node count of the data tree of an ensemble server.
+pub const JMX_ENSEMBLE_ZNODE_COUNT: &str = "zookeeper_InMemoryDataTree_NodeCount";
+/// Node count of the data tree of a standalone server.
+pub const JMX_STANDALONE_ZNODE_COUNT: &str = "zookeeper_NodeCount";
+/// Label with the role of an ensemble server ("Leader", "Follower" or "Observer").
+pub const JMX_MEMBER_TYPE_LABEL: &str = "memberType";
+/// Label with the id of an ensemble server.
+pub const JMX_REPLICA_ID_LABEL: &str = "replicaId";
+
+/// The key ZooKeeper metrics of a server, independent of the metrics format.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ZkMetrics {
+    /// Not reported by every metrics format.
+    pub server_state: Option<ServerState>,
+    pub znode_count: u64,
+    pub alive_connections: u64,
+    pub outstanding_requests: u64,
+    /// Only reported by the leader and only in the Prometheus format. The leader MBean exposes
+    /// its followers only as text (FollowerInfo), which the JMX exporter does not export, so
+    /// the follower count cannot be compared for the JMX exporter format.
+    pub followers: Option<u64>,
+}
+
+impl ZkMetrics {
+    /// Extract the key metrics in the given format. Fails if one is missing or not a
+    /// non-negative integer.
+    pub fn from_metrics(metrics: &Metrics, format: MetricsFormat) -> Result<ZkMetrics> {
+        let zk_metrics = match format {
+            MetricsFormat::JmxExporter => ZkMetrics::from_jmx_exporter(metrics)?,
+            MetricsFormat::Prometheus => ZkMetrics::from_prometheus(metrics)?,
+        };
+
+        if zk_metrics.znode_count == 0 {
+            return Err(anyhow!(
+                "Metrics report [0] znodes although the root znode always exists"
+            ));
+        }
+        Ok(zk_metrics)
+    }
+
+    fn from_jmx_exporter(metrics: &Metrics) -> Result<ZkMetrics> {
+        let connections = required_sample(metrics, &[JMX_ALIVE_CONNECTIONS])?;
+
+        let server_state = match (
+            connections.labels.get(JMX_MEMBER_TYPE_LABEL),
+            connections.labels.get(JMX_REPLICA_ID_LABEL),
+        ) {
+            (Some(member_type), _) => Some(ServerState::parse(&member_type.to_lowercase())?),
+            (None, None) => Some(ServerState::Standalone),
+            (None, Some(_)) => None,
+        };
+
+        Ok(ZkMetrics {
+            server_state,
+            znode_count: count(required_sample(
+                metrics,
+                &[JMX_ENSEMBLE_ZNODE_COUNT, JMX_STANDALONE_ZNODE_COUNT],
+            )?)?,
+            alive_connections: count(connections)?,
+            outstanding_requests: count(required_sample(metrics, &[JMX_OUTSTANDING_REQUESTS])?)?,
+            followers: None,
+        })
+    }
+
+    /// The native metrics provider (3.6 onwards) uses the "mntr" keys as metric names.
+    fn from_prometheus(metrics: &Metrics) -> Result<ZkMetrics> {
+        Ok(ZkMetrics {
+            server_state: None,
+            znode_count: count(required_sample(metrics, &["znode_count"])?)?,
+            alive_connections: count(required_sample(metrics, &["num_alive_connections"])?)?,
+            outstanding_requests: count(required_sample(metrics, &["outstanding_requests"])?)?,
+            followers: metrics
+                .samples("learners")
+                .first()
+                .map(|sample| count(sample))
+                .transpose()?,
+        })
+    }
+}
+
+/// Request the metrics from the endpoint (format: <host>:<port>) and parse them.
+pub fn fetch_metrics(address: &str) -> Result<Metrics> {
+    let url = format!("http://{}/metrics", address);
+    let body = request(&url);
+    record_response(&url, &body);
+    Metrics::parse(&body?)
+}
+
+fn request(url: &str) -> Result<String> {
+    let response = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(METRICS_REQUEST_TIMEOUT))
+        .build()?
+        .get(url)
+        .send()?;
+
+    let status = response.status();
+    let body = response.text()?;
+    if !status.is_success() {
+        return Err(anyhow!(
+            "Metrics endpoint [{}] answered with status [{}]: {}",
+            url,
+            status,
+            body
+        ));
+    }
+    Ok(body)
+}
+
+/// The first sample of the first of the given names which is present.
+fn required_sample<'a>(metrics: &'a Metrics, names: &[&str]) -> Result<&'a Sample> {
+    names
+        .iter()
+        .find_map(|name| metrics.samples(name).into_iter().next())
+        .ok_or_else(|| anyhow!("Missing metric {:?}", names))
+}
+
+fn count(sample: &Sample) -> Result<u64> {
+    if sample.value.is_finite() && sample.value >= 0.0 && sample.value.fract() == 0.0 {
+        Ok(sample.value as u64)
+    } else {
+        Err(anyhow!(
+            "Metric [{}] has the value [{}] which is no valid count",
+            sample.name,
+            sample.value
+        ))
+    }
+}
@@ -0,0 +1,427 @@
+use crate::common::admin_server::AdminServerOptions;
+use crate::common::four_letter_commands::{ServerAccess, ServerState};
+use crate::common::metrics::{
+    JMX_ALIVE_CONNECTIONS, JMX_ENSEMBLE_ZNODE_COUNT, JMX_OUTSTANDING_REQUESTS,
+    JMX_STANDALONE_ZNODE_COUNT,
+};
+use anyhow::Result;
+use integration_test_commons::test::prelude::Pod;
+use serde_json::json;
+use stackable_zookeeper_crd::ZookeeperVersion;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Error the admin server returns while the server is not (yet) part of a running ensemble.
+pub const NOT_SERVING_ERROR: &str = "This ZooKeeper instance is not currently serving requests";
+
+/// A pod scheduled on the given node. The uid and the Ready condition are only set if given.
+pub fn mock_pod(name: &str, node_name: &str, uid: Option<&str>, ready: Option<bool>) -> Pod {
+    let mut pod = json!({
+        "metadata": { "name": name },
+        "spec": { "containers": [], "nodeName": node_name },
+    });
+    if let Some(uid) = uid {
+        pod["metadata"]["uid"] = json!(uid);
+    }
+    if let Some(ready) = ready {
+        pod["status"] = json!({
+            "conditions": [{ "type": "Ready", "status": if ready { "True" } else { "False" } }]
+        });
+    }
+    serde_json::from_value(pod).unwrap()
+}
+
+/// Scriptable state of a mock server.
+#[derive(Clone, Debug)]
+pub struct MockState {
+    pub version: String,
+    pub mode: ServerState,
+    /// Before this instant the raw "ruok" is not answered and all other commands report
+    /// NOT_SERVING_ERROR.
+    pub ready_at: Instant,
+    /// Whitelisted raw four letter words. None whitelists all commands.
+    pub whitelist: Option<Vec<String>>,
+    /// Admin server commands answered with the given error.
+    pub errors: Vec<(String, String)>,
+    pub server_id: u64,
+    pub client_port: u16,
+    pub znode_count: u64,
+    pub outstanding_requests: u64,
+    /// Reported by the leader only.
+    pub followers: u64,
+    /// Reported by the leader only.
+    pub synced_followers: u64,
+    /// Served on "/metrics" instead of the JMX exporter metrics generated from this state (e.g.
+    /// to serve garbage).
+    pub metrics: Option<String>,
+}
+
+impl Default for MockState {
+    fn default() -> Self {
+        MockState {
+            version: "3.5.8-mock".to_string(),
+            mode: ServerState::Standalone,
+            ready_at: Instant::now(),
+            whitelist: None,
+            errors: vec![],
+            server_id: 1,
+            client_port: 2181,
+            znode_count: 5,
+            outstanding_requests: 0,
+            followers: 0,
+            synced_followers: 0,
+            metrics: None,
+        }
+    }
+}
+
+/// A local stand-in for a ZooKeeper server. It answers raw four letter words (like 3.4.x),
+/// admin server http requests (like 3.5.x) and JMX exporter metrics on "/metrics" on the same
+/// port, which allows to run the checks without a cluster. The server stops when dropped.
+pub struct MockZkServer {
+    ip: String,
+    port: u16,
+    state: Arc<Mutex<MockState>>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl MockZkServer {
+    /// Start a server on a random port of 127.0.0.1.
+    pub fn start() -> Result<Self> {
+        Self::start_on("127.0.0.1", 0)
+    }
+
+    /// Start a server on the given loopback ip and port (0 for a random port).
+    pub fn start_on(ip: &str, port: u16) -> Result<Self> {
+        let listener = TcpListener::bind((ip, port))?;
+        let server = MockZkServer {
+            ip: ip.to_string(),
+            port: listener.local_addr()?.port(),
+            state: Arc::new(Mutex::new(MockState::default())),
+            stopped: Arc::new(AtomicBool::new(false)),
+        };
+
+        let state = server.state.clone();
+        let stopped = server.stopped.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                if stopped.load(Ordering::SeqCst) {
+                    break;
+                }
+                if let Ok(stream) = stream {
+                    let state = state.lock().unwrap().clone();
+                    // errors are expected when clients give up early
+                    let _ = handle_connection(stream, &state);
+                }
+            }
+        });
+
+        Ok(server)
+    }
+
+    /// Start one server per server state on 127.0.0.1, 127.0.0.2, ... sharing the same port,
+    /// because the checks address all pods with one port. This requires the whole 127.0.0.0/8
+    /// range to be routed to the loopback interface (default on Linux). The leader reports the other servers
+    /// as followers.
+    pub fn start_ensemble(modes: &[ServerState]) -> Result<Vec<MockZkServer>> {
+        let mut servers: Vec<MockZkServer> = Vec::new();
+        for (index, mode) in modes.iter().enumerate() {
+            let port = servers.first().map_or(0, |server| server.port);
+            let server = Self::start_on(&format!("127.0.0.{}", index + 1), port)?;
+            server.update(|state| {
+                state.mode = *mode;
+                state.server_id = index as u64 + 1;
+                state.followers = modes.len() as u64 - 1;
+                state.synced_followers = modes.len() as u64 - 1;
+            });
+            servers.push(server);
+        }
+        Ok(servers)
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// The ip of the server, which is used as host name by the checks.
+    pub fn host_name(&self) -> &str {
+        &self.ip
+    }
+
+    /// The address (format: <ip>:<port>) of the server.
+    pub fn address(&self) -> String {
+        format!("{}:{}", self.ip, self.port)
+    }
+
+    /// Access to the server as the given version, with four letter words and admin server
+    /// commands sent to the port of the mock.
+    pub fn access(&self, version: &ZookeeperVersion) -> Result<ServerAccess> {
+        Ok(ServerAccess::new(version)?
+            .client_port(self.port)
+            .admin_server(AdminServerOptions {
+                port: self.port,
+                ..AdminServerOptions::default()
+            }))
+    }
+
+    /// A pod scheduled on a node named after the ip of this server.
+    pub fn pod(&self) -> Pod {
+        mock_pod(&format!("zookeeper-{}", self.ip), &self.ip, None, None)
+    }
+
+    /// Modify the state of the server, e.g. `server.update(|state| state.mode = Leader)`.
+    pub fn update<F: FnOnce(&mut MockState)>(&self, update: F) {
+        update(&mut self.state.lock().unwrap());
+    }
+
+    /// The server becomes ready after the given delay.
+    pub fn ready_after(&self, delay: Duration) {
+        self.update(|state| state.ready_at = Instant::now() + delay);
+    }
+
+    /// Only the given raw four letter words are answered.
+    pub fn whitelist(&self, commands: &[&str]) {
+        self.update(|state| {
+            state.whitelist = Some(commands.iter().map(|c| c.to_string()).collect())
+        });
+    }
+
+    /// The admin server answers the given command with an error.
+    pub fn fail_command(&self, command: &str, error: &str) {
+        self.update(|state| state.errors.push((command.to_string(), error.to_string())));
+    }
+}
+
+impl Drop for MockZkServer {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // wake up the accept loop
+        let _ = TcpStream::connect((self.ip.as_str(), self.port));
+    }
+}
+
+fn handle_connection(mut stream: TcpStream, state: &MockState) -> Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+
+    let mut command = [0u8; 4];
+    stream.read_exact(&mut command)?;
+
+    if &command == b"GET " {
+        let mut reader = BufReader::new(&mut stream);
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line)?;
+        let mut header = String::new();
+        while reader.read_line(&mut header)? > 2 {
+            header.clear();
+        }
+
+        let path = request_line.split_whitespace().next().unwrap_or_default();
+        let (status, content_type, body) = match path.strip_prefix("/commands/") {
+            Some(command) => {
+                let (status, body) = admin_response(command, state);
+                (status, "application/json", body)
+            }
+            None if path == "/metrics" => (200, "text/plain; version=0.0.4", metrics(state)),
+            None => (404, "text/plain", String::new()),
+        };
+        write!(
+            stream,
+            "HTTP/1.1 {} Mock\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            content_type,
+            body.len(),
+            body
+        )?;
+    } else {
+        let command = String::from_utf8_lossy(&command).to_string();
+        if let Some(response) = four_letter_word_response(&command, state) {
+            stream.write_all(response.as_bytes())?;
+        }
+    }
+
+    stream.flush()?;
+    Ok(())
+}
+
+/// The raw response like 3.4.x would send it. None closes the connection without response.
+fn four_letter_word_response(command: &str, state: &MockState) -> Option<String> {
+    if let Some(whitelist) = &state.whitelist {
+        if !whitelist.iter().any(|c| c == command || c == "*") {
+            return Some(format!(
+                "{} is not executed because it is not in the whitelist.\n",
+                command
+            ));
+        }
+    }
+
+    // a server which is not running does not respond to "ruok" at all
+    let ready = Instant::now() >= state.ready_at;
+
+    match command {
+        "ruok" if ready => Some("imok".to_string()),
+        "mntr" | "srvr" | "stat" if !ready => Some(format!("{}\n", NOT_SERVING_ERROR)),
+        "mntr" => Some(
+            monitor_values(state)
+                .iter()
+                .map(|(key, value)| format!("zk_{}\t{}\n", key, value))
+                .collect(),
+        ),
+        "srvr" | "stat" => Some(format!(
+            "Zookeeper version: {}\nLatency min/avg/max: 0/0/0\nReceived: 1\nSent: 1\nConnections: 1\nOutstanding: {}\nZxid: 0x0\nMode: {}\nNode count: {}\n",
+            state.version, state.outstanding_requests, state.mode, state.znode_count
+        )),
+        "conf" => Some(format!(
+            "clientPort={}\ndataDir=/tmp/zookeeper/version-2\ntickTime=2000\nserverId={}\n",
+            state.client_port, state.server_id
+        )),
+        _ => None,
+    }
+}
+
+/// The admin server status code and JSON body like 3.5.x would send it.
+fn admin_response(command: &str, state: &MockState) -> (u16, String) {
+    let canonical = match command {
+        "mntr" => "monitor",
+        "conf" => "configuration",
+        "srvr" => "server_stats",
+        "lead" => "leader",
+        other => other,
+    };
+
+    let error = state
+        .errors
+        .iter()
+        .find(|(failing, _)| failing == command || failing == canonical)
+        .map(|(_, error)| error.clone())
+        .or_else(|| {
+            if Instant::now() < state.ready_at {
+                Some(NOT_SERVING_ERROR.to_string())
+            } else {
+                None
+            }
+        });
+
+    let mut body = match (canonical, &error) {
+        (_, Some(_)) => json!({}),
+        ("ruok", None) => json!({}),
+        ("monitor", None) => {
+            let mut values = serde_json::Map::new();
+            for (key, value) in monitor_values(state) {
+                let value = value
+                    .parse::<i64>()
+                    .map(|number| json!(number))
+                    .unwrap_or_else(|_| json!(value));
+                values.insert(key, value);
+            }
+            json!(values)
+        }
+        ("configuration", None) => json!({
+            "client_port": state.client_port,
+            "data_dir": "/tmp/zookeeper/version-2",
+            "tick_time": 2000,
+            "server_id": state.server_id,
+        }),
+        ("server_stats", None) => json!({
+            "version": state.version,
+            "read_only": false,
+            "server_stats": {
+                "packets_sent": 1,
+                "packets_received": 1,
+                "max_latency": 0,
+                "min_latency": 0,
+                "avg_latency": 0,
+                "outstanding_requests": state.outstanding_requests,
+                "server_state": state.mode.to_string(),
+                "num_alive_client_connections": 1,
+            },
+            "node_count": state.znode_count,
+        }),
+        ("leader", None) => json!({ "is_leader": state.mode == ServerState::Leader }),
+        _ => {
+            return (
+                404,
+                json!({ "command": command, "error": "Command not found" }).to_string(),
+            )
+        }
+    };
+
+    body["command"] = json!(canonical);
+    body["error"] = json!(error);
+    (200, body.to_string())
+}
+
+/// The metrics like the JMX exporter would serve them. Servers of an ensemble report their
+/// replica id and role as labels.
+fn metrics(state: &MockState) -> String {
+    if let Some(metrics) = &state.metrics {
+        return metrics.clone();
+    }
+
+    let (labels, znode_count) = if state.mode == ServerState::Standalone {
+        (String::new(), JMX_STANDALONE_ZNODE_COUNT)
+    } else {
+        let member_type = state.mode.to_string();
+        (
+            format!(
+                "{{replicaId=\"{}\",memberType=\"{}{}\",}}",
+                state.server_id,
+                member_type[..1].to_uppercase(),
+                &member_type[1..]
+            ),
+            JMX_ENSEMBLE_ZNODE_COUNT,
+        )
+    };
+
+    let mut metrics = String::from(
+        "# HELP jmx_scrape_duration_seconds Time this JMX scrape took, in seconds.\n# TYPE jmx_scrape_duration_seconds gauge\njmx_scrape_duration_seconds 0.012\n",
+    );
+    for (name, value) in &[
+        (JMX_ALIVE_CONNECTIONS, 1),
+        (JMX_OUTSTANDING_REQUESTS, state.outstanding_requests),
+        (znode_count, state.znode_count),
+    ] {
+        metrics.push_str(&format!(
+            "# HELP {name} {name} (mock)\n# TYPE {name} untyped\n{name}{labels} {value}.0\n",
+            name = name,
+            labels = labels,
+            value = value
+        ));
+    }
+    metrics
+}
+
+fn monitor_values(state: &MockState) -> Vec<(String, String)> {
+    let mut values = vec![
+        ("version", state.version.clone()),
+        ("avg_latency", "0".to_string()),
+        ("max_latency", "0".to_string()),
+        ("min_latency", "0".to_string()),
+        ("packets_received", "1".to_string()),
+        ("packets_sent", "1".to_string()),
+        ("num_alive_connections", "1".to_string()),
+        (
+            "outstanding_requests",
+            state.outstanding_requests.to_string(),
+        ),
+        ("server_state", state.mode.to_string()),
+        ("znode_count", state.znode_count.to_string()),
+        ("watch_count", "0".to_string()),
+        ("ephemerals_count", "0".to_string()),
+        ("approximate_data_size", "27".to_string()),
+    ];
+
+    if state.mode == ServerState::Leader {
+        values.push(("followers", state.followers.to_string()));
+        values.push(("synced_followers", state.synced_followers.to_string()));
+        values.push(("pending_syncs", "0".to_string()));
+    }
+
+    values
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect()
+}
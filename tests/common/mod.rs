@@ -1,3 +1,16 @@
+pub mod admin_server;
+pub mod capabilities;
 pub mod checks;
+pub mod diagnostics;
+pub mod discovery;
 pub mod four_letter_commands;
+pub mod known_issues;
+pub mod kube_api;
+pub mod metrics;
+pub mod mock_server;
+pub mod prometheus;
+pub mod retry;
+pub mod traffic;
+pub mod zk_client;
+pub mod zoo_cfg;
 pub mod zookeeper;
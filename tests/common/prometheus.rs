@@ -0,0 +1,455 @@
+use anyhow::{anyhow, Result};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Label which holds the upper bound of a histogram bucket.
+const BUCKET_LABEL: &str = "le";
+
+/// Type of a metric family as declared via "# TYPE".
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MetricType {
+    Counter,
+    Gauge,
+    Histogram,
+    Summary,
+    Untyped,
+}
+
+impl FromStr for MetricType {
+    type Err = anyhow::Error;
+
+    fn from_str(metric_type: &str) -> Result<Self> {
+        match metric_type {
+            "counter" => Ok(MetricType::Counter),
+            "gauge" => Ok(MetricType::Gauge),
+            "histogram" => Ok(MetricType::Histogram),
+            "summary" => Ok(MetricType::Summary),
+            "untyped" => Ok(MetricType::Untyped),
+            other => Err(anyhow!("Unknown metric type [{}]", other)),
+        }
+    }
+}
+
+impl fmt::Display for MetricType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let metric_type = match self {
+            MetricType::Counter => "counter",
+            MetricType::Gauge => "gauge",
+            MetricType::Histogram => "histogram",
+            MetricType::Summary => "summary",
+            MetricType::Untyped => "untyped",
+        };
+        write!(f, "{}", metric_type)
+    }
+}
+
+/// A single sample line, e.g. `zookeeper_NumAliveConnections{memberType="Leader",} 1.0`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sample {
+    pub name: String,
+    pub labels: BTreeMap<String, String>,
+    pub value: f64,
+    /// Milliseconds since epoch, if provided.
+    pub timestamp: Option<i64>,
+}
+
+/// All samples of a metric. Histograms and summaries contain the samples of their "_bucket",
+/// "_sum" and "_count" series.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MetricFamily {
+    pub name: String,
+    pub help: Option<String>,
+    pub metric_type: MetricType,
+    pub samples: Vec<Sample>,
+}
+
+/// One histogram of a family, i.e. all samples sharing the same labels except "le".
+#[derive(Clone, Debug, PartialEq)]
+pub struct Histogram {
+    pub labels: BTreeMap<String, String>,
+    /// Upper bound and cumulative count of every bucket, ordered by the upper bound. The last
+    /// bucket is always "+Inf".
+    pub buckets: Vec<(f64, f64)>,
+    pub sum: f64,
+    pub count: f64,
+}
+
+/// Metrics in the Prometheus text exposition format (version 0.0.4) as served by the JMX
+/// exporter.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Metrics {
+    pub families: BTreeMap<String, MetricFamily>,
+}
+
+impl Metrics {
+    /// Parse the exposition format. Every line has to be a comment, a HELP or TYPE
+    /// declaration or a valid sample, so garbage is rejected instead of being skipped.
+    pub fn parse(text: &str) -> Result<Metrics> {
+        let mut metrics = Metrics::default();
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            let result = if line.is_empty() {
+                Ok(())
+            } else if let Some(comment) = line.strip_prefix('#') {
+                metrics.parse_comment(comment.trim_start())
+            } else {
+                parse_sample(line).map(|sample| metrics.add_sample(sample))
+            };
+
+            result.map_err(|err| {
+                anyhow!("Invalid metrics line [{}] [{}]: {}", index + 1, line, err)
+            })?;
+        }
+
+        Ok(metrics)
+    }
+
+    pub fn family(&self, name: &str) -> Option<&MetricFamily> {
+        self.families.get(name)
+    }
+
+    /// All samples with the given name (e.g. "jvm_gc_collection_seconds_count").
+    pub fn samples(&self, name: &str) -> Vec<&Sample> {
+        self.families
+            .values()
+            .flat_map(|family| family.samples.iter())
+            .filter(|sample| sample.name == name)
+            .collect()
+    }
+
+    /// The value of the first sample with the given name.
+    pub fn value(&self, name: &str) -> Option<f64> {
+        self.samples(name).first().map(|sample| sample.value)
+    }
+
+    /// The histograms of the given family. Fails if the family is no histogram or if the
+    /// buckets are inconsistent (not cumulative, missing "+Inf" bucket or a count which does
+    /// not match the "+Inf" bucket).
+    pub fn histograms(&self, name: &str) -> Result<Vec<Histogram>> {
+        let family = self
+            .family(name)
+            .ok_or_else(|| anyhow!("Missing histogram [{}]", name))?;
+        if family.metric_type != MetricType::Histogram {
+            return Err(anyhow!(
+                "Metric [{}] is a [{}] instead of a histogram",
+                name,
+                family.metric_type
+            ));
+        }
+
+        let mut histograms: BTreeMap<Vec<(String, String)>, Histogram> = BTreeMap::new();
+        for sample in &family.samples {
+            let mut labels = sample.labels.clone();
+            let bound = labels.remove(BUCKET_LABEL);
+            let histogram = histograms
+                .entry(labels.clone().into_iter().collect())
+                .or_insert_with(|| Histogram {
+                    labels,
+                    buckets: vec![],
+                    sum: f64::NAN,
+                    count: f64::NAN,
+                });
+
+            match sample.name.strip_prefix(name) {
+                Some("_bucket") => {
+                    let bound = bound.ok_or_else(|| {
+                        anyhow!(
+                            "Bucket of histogram [{}] without [{}] label",
+                            name,
+                            BUCKET_LABEL
+                        )
+                    })?;
+                    histogram.buckets.push((parse_value(&bound)?, sample.value));
+                }
+                Some("_sum") => histogram.sum = sample.value,
+                Some("_count") => histogram.count = sample.value,
+                _ => {
+                    return Err(anyhow!(
+                        "Unexpected sample [{}] in histogram [{}]",
+                        sample.name,
+                        name
+                    ))
+                }
+            }
+        }
+
+        for histogram in histograms.values_mut() {
+            histogram
+                .buckets
+                .sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+            verify_histogram(name, histogram)?;
+        }
+
+        Ok(histograms.into_values().collect())
+    }
+
+    fn parse_comment(&mut self, comment: &str) -> Result<()> {
+        let mut parts = comment.splitn(3, ' ');
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some("HELP"), Some(name), help) => {
+                self.family_mut(name)?.help = Some(unescape(help.unwrap_or_default(), false)?);
+                Ok(())
+            }
+            (Some("TYPE"), Some(name), Some(metric_type)) => {
+                let metric_type = metric_type.trim().parse()?;
+                let family = self.family_mut(name)?;
+                if !family.samples.is_empty() {
+                    return Err(anyhow!("TYPE of [{}] declared after its samples", name));
+                }
+                family.metric_type = metric_type;
+                Ok(())
+            }
+            (Some("TYPE"), _, _) => Err(anyhow!("TYPE without metric name and type")),
+            (Some("HELP"), _, _) => Err(anyhow!("HELP without metric name")),
+            // any other comment is ignored
+            _ => Ok(()),
+        }
+    }
+
+    fn add_sample(&mut self, sample: Sample) {
+        let family_name = self
+            .families
+            .values()
+            .find(|family| belongs_to(family, &sample.name))
+            .map(|family| family.name.clone())
+            .unwrap_or_else(|| sample.name.clone());
+
+        self.families
+            .entry(family_name.clone())
+            .or_insert_with(|| new_family(&family_name))
+            .samples
+            .push(sample);
+    }
+
+    fn family_mut(&mut self, name: &str) -> Result<&mut MetricFamily> {
+        validate_metric_name(name)?;
+        Ok(self
+            .families
+            .entry(name.to_string())
+            .or_insert_with(|| new_family(name)))
+    }
+}
+
+fn new_family(name: &str) -> MetricFamily {
+    MetricFamily {
+        name: name.to_string(),
+        help: None,
+        metric_type: MetricType::Untyped,
+        samples: vec![],
+    }
+}
+
+/// Histograms and summaries consist of several series named after the family.
+fn belongs_to(family: &MetricFamily, sample_name: &str) -> bool {
+    if family.name == sample_name {
+        return true;
+    }
+
+    let suffixes: &[&str] = match family.metric_type {
+        MetricType::Histogram => &["_bucket", "_sum", "_count"],
+        MetricType::Summary => &["_sum", "_count"],
+        _ => &[],
+    };
+    matches!(
+        sample_name.strip_prefix(&family.name),
+        Some(suffix) if suffixes.contains(&suffix)
+    )
+}
+
+fn verify_histogram(name: &str, histogram: &Histogram) -> Result<()> {
+    match histogram.buckets.last() {
+        Some((bound, count)) if bound.is_infinite() && *bound > 0.0 => {
+            if *count != histogram.count {
+                return Err(anyhow!(
+                    "Histogram [{}] {:?} counts [{}] in the +Inf bucket but reports a count of [{}]",
+                    name,
+                    histogram.labels,
+                    count,
+                    histogram.count
+                ));
+            }
+        }
+        _ => {
+            return Err(anyhow!(
+                "Histogram [{}] {:?} has no +Inf bucket",
+                name,
+                histogram.labels
+            ))
+        }
+    }
+
+    if histogram.sum.is_nan() {
+        return Err(anyhow!(
+            "Histogram [{}] {:?} has no sum",
+            name,
+            histogram.labels
+        ));
+    }
+
+    if histogram
+        .buckets
+        .windows(2)
+        .any(|buckets| buckets[1].1 < buckets[0].1)
+    {
+        return Err(anyhow!(
+            "Buckets of histogram [{}] {:?} are not cumulative: {:?}",
+            name,
+            histogram.labels,
+            histogram.buckets
+        ));
+    }
+
+    Ok(())
+}
+
+fn parse_sample(line: &str) -> Result<Sample> {
+    let name_end = line
+        .find(|c: char| c == '{' || c.is_whitespace())
+        .ok_or_else(|| anyhow!("Sample without value"))?;
+    let name = &line[..name_end];
+    validate_metric_name(name)?;
+
+    let (labels, rest) = if line[name_end..].starts_with('{') {
+        parse_labels(&line[name_end + 1..])?
+    } else {
+        (BTreeMap::new(), &line[name_end..])
+    };
+
+    let mut parts = rest.split_whitespace();
+    let value = parse_value(
+        parts
+            .next()
+            .ok_or_else(|| anyhow!("Sample without value"))?,
+    )?;
+    let timestamp = parts
+        .next()
+        .map(|timestamp| {
+            timestamp
+                .parse()
+                .map_err(|_| anyhow!("Invalid timestamp [{}]", timestamp))
+        })
+        .transpose()?;
+    if let Some(unexpected) = parts.next() {
+        return Err(anyhow!("Unexpected [{}] after the timestamp", unexpected));
+    }
+
+    Ok(Sample {
+        name: name.to_string(),
+        labels,
+        value,
+        timestamp,
+    })
+}
+
+/// Parses `name="value",...}` (the opening brace is already consumed) and returns the labels
+/// and the remaining line. A trailing comma is allowed, as written by the JMX exporter.
+fn parse_labels(mut rest: &str) -> Result<(BTreeMap<String, String>, &str)> {
+    let mut labels = BTreeMap::new();
+
+    loop {
+        rest = rest.trim_start();
+        if let Some(remaining) = rest.strip_prefix('}') {
+            return Ok((labels, remaining));
+        }
+
+        let (name, remaining) = rest
+            .split_once('=')
+            .ok_or_else(|| anyhow!("Label without value in [{}]", rest))?;
+        let name = name.trim();
+        validate_label_name(name)?;
+
+        let remaining = remaining
+            .trim_start()
+            .strip_prefix('"')
+            .ok_or_else(|| anyhow!("Value of label [{}] is not quoted", name))?;
+        let end = closing_quote(remaining)
+            .ok_or_else(|| anyhow!("Value of label [{}] is not terminated", name))?;
+        if labels
+            .insert(name.to_string(), unescape(&remaining[..end], true)?)
+            .is_some()
+        {
+            return Err(anyhow!("Duplicate label [{}]", name));
+        }
+
+        rest = remaining[end + 1..].trim_start();
+        if let Some(remaining) = rest.strip_prefix(',') {
+            rest = remaining;
+        } else if !rest.starts_with('}') {
+            return Err(anyhow!("Expected [,] or [}}] after label [{}]", name));
+        }
+    }
+}
+
+/// Index of the first quote which is not escaped.
+fn closing_quote(value: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (index, c) in value.char_indices() {
+        match c {
+            '\\' if !escaped => escaped = true,
+            '"' if !escaped => return Some(index),
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+/// Label values may contain escaped backslashes, quotes and line feeds, HELP texts only
+/// backslashes and line feeds.
+fn unescape(escaped: &str, quotes: bool) -> Result<String> {
+    let mut unescaped = String::with_capacity(escaped.len());
+    let mut chars = escaped.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => unescaped.push('\\'),
+            Some('n') => unescaped.push('\n'),
+            Some('"') if quotes => unescaped.push('"'),
+            // unknown escape sequences are kept as they are in HELP texts
+            Some(other) if !quotes => {
+                unescaped.push('\\');
+                unescaped.push(other);
+            }
+            other => {
+                return Err(anyhow!(
+                    "Invalid escape sequence [\\{}]",
+                    other.unwrap_or(' ')
+                ))
+            }
+        }
+    }
+    Ok(unescaped)
+}
+
+fn parse_value(value: &str) -> Result<f64> {
+    match value {
+        "+Inf" | "Inf" => Ok(f64::INFINITY),
+        "-Inf" => Ok(f64::NEG_INFINITY),
+        "NaN" => Ok(f64::NAN),
+        _ => value
+            .parse()
+            .map_err(|_| anyhow!("Invalid sample value [{}]", value)),
+    }
+}
+
+fn validate_metric_name(name: &str) -> Result<()> {
+    validate_name(name, |c| c == ':').map_err(|_| anyhow!("Invalid metric name [{}]", name))
+}
+
+fn validate_label_name(name: &str) -> Result<()> {
+    validate_name(name, |_| false).map_err(|_| anyhow!("Invalid label name [{}]", name))
+}
+
+/// Names consist of ASCII letters, digits, underscores and additional characters and must not
+/// start with a digit.
+fn validate_name(name: &str, additional: fn(char) -> bool) -> Result<()> {
+    let valid = |c: char| c.is_ascii_alphanumeric() || c == '_' || additional(c);
+    match name.chars().next() {
+        Some(first) if !first.is_ascii_digit() && name.chars().all(valid) => Ok(()),
+        _ => Err(anyhow!("Invalid name [{}]", name)),
+    }
+}
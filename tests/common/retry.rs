@@ -0,0 +1,255 @@
+use anyhow::{anyhow, Result};
+use std::env;
+use std::fmt;
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Multiplies the max duration of every retry policy, e.g. "2.5" on slow CI nodes.
+pub const ENV_TIMEOUT_FACTOR: &str = "ZK_TEST_RETRY_TIMEOUT_FACTOR";
+/// Replaces the default delay (in milliseconds) before the second attempt.
+pub const ENV_INITIAL_DELAY_MILLIS: &str = "ZK_TEST_RETRY_INITIAL_DELAY_MILLIS";
+/// Replaces the default upper bound (in milliseconds) of the delay between two attempts.
+pub const ENV_MAX_DELAY_MILLIS: &str = "ZK_TEST_RETRY_MAX_DELAY_MILLIS";
+/// Replaces the default factor the delay is multiplied with after every attempt.
+pub const ENV_BACKOFF_FACTOR: &str = "ZK_TEST_RETRY_BACKOFF_FACTOR";
+/// Replaces the default jitter, i.e. the fraction (0.0 - 1.0) by which a delay is randomly shortened.
+pub const ENV_JITTER: &str = "ZK_TEST_RETRY_JITTER";
+
+const DEFAULT_INITIAL_DELAY_MILLIS: u64 = 1000;
+const DEFAULT_MAX_DELAY_MILLIS: u64 = 10_000;
+const DEFAULT_BACKOFF_FACTOR: f64 = 2.0;
+const DEFAULT_JITTER: f64 = 0.2;
+
+/// Decides whether an attempt failing with the given error should be repeated.
+pub type RetryClassifier = fn(&anyhow::Error) -> bool;
+
+/// Repeats an operation with exponential backoff until it succeeds, a non-retryable error
+/// occurs or the max duration is exceeded. The ENV_* variables only replace the defaults of
+/// settings which are not set in code, so CI can stretch the durations without changing the
+/// behavior a check relies on. The ENV_TIMEOUT_FACTOR always scales the max duration.
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    max_duration: Duration,
+    initial_delay: Option<Duration>,
+    max_delay: Option<Duration>,
+    backoff_factor: Option<f64>,
+    jitter: Option<f64>,
+    retryable: RetryClassifier,
+}
+
+/// The settings of a policy after the environment variables were applied.
+struct EffectivePolicy {
+    max_duration: Duration,
+    initial_delay: Duration,
+    max_delay: Duration,
+    backoff_factor: f64,
+    jitter: f64,
+    retryable: RetryClassifier,
+}
+
+/// The outcome of a single failed attempt.
+#[derive(Clone, Debug)]
+pub struct Attempt {
+    pub number: usize,
+    pub elapsed: Duration,
+    pub error: String,
+}
+
+impl fmt::Display for Attempt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "attempt [{}] after [{}] ms: {}",
+            self.number,
+            self.elapsed.as_millis(),
+            self.error
+        )
+    }
+}
+
+impl RetryPolicy {
+    /// Retry every error for at most max_duration, starting with a delay of one second which
+    /// doubles after every attempt up to 10 seconds, shortened by a jitter of up to 20 percent.
+    pub fn new(max_duration: Duration) -> Self {
+        RetryPolicy {
+            max_duration,
+            initial_delay: None,
+            max_delay: None,
+            backoff_factor: None,
+            jitter: None,
+            retryable: retry_all,
+        }
+    }
+
+    pub fn initial_delay(mut self, initial_delay: Duration) -> Self {
+        self.initial_delay = Some(initial_delay);
+        self
+    }
+
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = Some(max_delay);
+        self
+    }
+
+    /// A factor of 1.0 results in a constant delay.
+    pub fn backoff_factor(mut self, backoff_factor: f64) -> Self {
+        self.backoff_factor = Some(backoff_factor);
+        self
+    }
+
+    pub fn jitter(mut self, jitter: f64) -> Self {
+        self.jitter = Some(jitter);
+        self
+    }
+
+    /// Only repeat attempts failing with errors accepted by the classifier, e.g.
+    /// `is_network_error`. Other errors are returned immediately.
+    pub fn retry_if(mut self, retryable: RetryClassifier) -> Self {
+        self.retryable = retryable;
+        self
+    }
+
+    /// Run the operation until it succeeds. Every failed attempt is printed. If the operation
+    /// does not succeed, the returned error lists all attempts.
+    pub fn retry<T, F>(&self, operation: &str, mut f: F) -> Result<T>
+    where
+        F: FnMut() -> Result<T>,
+    {
+        let policy = self.effective()?;
+        let start = Instant::now();
+        let mut delay = policy.initial_delay;
+        let mut attempts: Vec<Attempt> = Vec::new();
+
+        loop {
+            let err = match f() {
+                Ok(value) => return Ok(value),
+                Err(err) => err,
+            };
+
+            let attempt = Attempt {
+                number: attempts.len() + 1,
+                elapsed: start.elapsed(),
+                error: format!("{:#}", err),
+            };
+            println!("[{}] Failed {}", operation, attempt);
+            attempts.push(attempt);
+
+            if !(policy.retryable)(&err) {
+                return Err(policy.report(operation, "Non-retryable error", &attempts));
+            }
+
+            // the last attempt is made when the max duration is reached
+            let remaining = match policy.max_duration.checked_sub(start.elapsed()) {
+                Some(remaining) if !remaining.is_zero() => remaining,
+                _ => return Err(policy.report(operation, "Timeout", &attempts)),
+            };
+
+            thread::sleep(policy.apply_jitter(delay).min(remaining));
+            delay = delay.mul_f64(policy.backoff_factor).min(policy.max_delay);
+        }
+    }
+
+    /// Resolve every setting: values set in code win over the environment variables, which
+    /// win over the defaults.
+    fn effective(&self) -> Result<EffectivePolicy> {
+        let policy = EffectivePolicy {
+            max_duration: scaled_timeout(self.max_duration)?,
+            initial_delay: match self.initial_delay {
+                Some(initial_delay) => initial_delay,
+                None => Duration::from_millis(
+                    env_var(ENV_INITIAL_DELAY_MILLIS)?.unwrap_or(DEFAULT_INITIAL_DELAY_MILLIS),
+                ),
+            },
+            max_delay: match self.max_delay {
+                Some(max_delay) => max_delay,
+                None => Duration::from_millis(
+                    env_var(ENV_MAX_DELAY_MILLIS)?.unwrap_or(DEFAULT_MAX_DELAY_MILLIS),
+                ),
+            },
+            backoff_factor: match self.backoff_factor {
+                Some(backoff_factor) => backoff_factor,
+                None => env_var(ENV_BACKOFF_FACTOR)?.unwrap_or(DEFAULT_BACKOFF_FACTOR),
+            },
+            jitter: match self.jitter {
+                Some(jitter) => jitter,
+                None => env_var(ENV_JITTER)?.unwrap_or(DEFAULT_JITTER),
+            },
+            retryable: self.retryable,
+        };
+
+        if policy.backoff_factor < 1.0 || !(0.0..=1.0).contains(&policy.jitter) {
+            return Err(anyhow!(
+                "Invalid retry policy: the backoff factor [{}] must be at least 1.0 and the jitter [{}] between 0.0 and 1.0",
+                policy.backoff_factor,
+                policy.jitter
+            ));
+        }
+
+        Ok(policy)
+    }
+}
+
+impl EffectivePolicy {
+    fn report(&self, operation: &str, reason: &str, attempts: &[Attempt]) -> anyhow::Error {
+        let attempts: Vec<String> = attempts
+            .iter()
+            .map(|attempt| format!("  {}", attempt))
+            .collect();
+        anyhow!(
+            "{} for [{}] after [{}] attempts within the max duration of [{}] ms:\n{}",
+            reason,
+            operation,
+            attempts.len(),
+            self.max_duration.as_millis(),
+            attempts.join("\n")
+        )
+    }
+
+    fn apply_jitter(&self, delay: Duration) -> Duration {
+        // uniformly distributed random number between 0.0 and 1.0
+        let random = Uuid::new_v4().as_fields().0 as f64 / u32::MAX as f64;
+        delay.mul_f64(1.0 - self.jitter * random)
+    }
+}
+
+/// Multiply the timeout with the ENV_TIMEOUT_FACTOR (if set). Used for time budgets which are
+/// asserted directly instead of via a retry policy.
+pub fn scaled_timeout(timeout: Duration) -> Result<Duration> {
+    match env_var::<f64>(ENV_TIMEOUT_FACTOR)? {
+        Some(factor) if factor > 0.0 => Ok(timeout.mul_f64(factor)),
+        Some(factor) => Err(anyhow!(
+            "The timeout factor [{}] must be greater than 0.0",
+            factor
+        )),
+        None => Ok(timeout),
+    }
+}
+
+/// Every error is retryable.
+pub fn retry_all(_: &anyhow::Error) -> bool {
+    true
+}
+
+/// Only errors caused by the network (e.g. connection refused, timeouts or failed http
+/// requests) are retryable. Malformed responses fail immediately.
+pub fn is_network_error(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        cause.downcast_ref::<std::io::Error>().is_some()
+            || cause.downcast_ref::<reqwest::Error>().is_some()
+    })
+}
+
+fn env_var<T: FromStr>(name: &str) -> Result<Option<T>> {
+    match env::var(name) {
+        Ok(value) => value.trim().parse().map(Some).map_err(|_| {
+            anyhow!(
+                "Invalid value [{}] for environment variable [{}]",
+                value,
+                name
+            )
+        }),
+        Err(_) => Ok(None),
+    }
+}
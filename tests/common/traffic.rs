@@ -0,0 +1,185 @@
+use crate::common::zk_client::{is_zk_error, CreateMode, ZkClient, ERROR_NO_NODE};
+use anyhow::{anyhow, Result};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Pause between two write / read rounds of the background traffic.
+const TRAFFIC_INTERVAL_MILLIS: u64 = 100;
+
+/// Continuously writes znodes and reads them back via the native client while the ensemble is
+/// modified (e.g. during a rolling upgrade). Requests fail over to the next host if a server is
+/// not reachable. Every acknowledged write is remembered, so it can be verified afterwards
+/// that no acknowledged write was lost.
+pub struct BackgroundTraffic {
+    stopped: Arc<AtomicBool>,
+    handle: JoinHandle<Result<TrafficReport>>,
+}
+
+/// The outcome of the background traffic.
+#[derive(Clone, Debug, Default)]
+pub struct TrafficReport {
+    /// Parent znode of all written znodes.
+    pub root: String,
+    /// Path and data of every write acknowledged by the ensemble.
+    pub acknowledged: Vec<(String, Vec<u8>)>,
+    /// Writes which failed or whose result is unknown (e.g. the connection was lost).
+    pub failed_writes: usize,
+    pub successful_reads: usize,
+    pub failed_reads: usize,
+    /// Acknowledged writes which were missing or contained other data when read back.
+    pub lost: Vec<String>,
+    /// Longest period without any successful request, i.e. the ensemble had no quorum or no
+    /// server was reachable.
+    pub longest_outage: Duration,
+    pub duration: Duration,
+}
+
+impl BackgroundTraffic {
+    /// Start the traffic against the servers (format: <host>:<port>). The root znode is created
+    /// before this returns, so the ensemble has to be available at this point.
+    pub fn start(hosts: Vec<String>) -> Result<Self> {
+        if hosts.is_empty() {
+            return Err(anyhow!("No hosts provided for the background traffic"));
+        }
+
+        let root = format!("/integration-test-traffic-{}", Uuid::new_v4());
+        let mut client = ZkClient::connect(&hosts[0])?;
+        client.create(&root, &[], CreateMode::Persistent)?;
+        client.close()?;
+
+        let stopped = Arc::new(AtomicBool::new(false));
+        let traffic_stopped = stopped.clone();
+        let handle = thread::spawn(move || run_traffic(&hosts, root, &traffic_stopped));
+
+        Ok(BackgroundTraffic { stopped, handle })
+    }
+
+    /// Stop the traffic and return the report.
+    pub fn stop(self) -> Result<TrafficReport> {
+        self.stopped.store(true, Ordering::SeqCst);
+        self.handle
+            .join()
+            .map_err(|_| anyhow!("Background traffic thread panicked"))?
+    }
+}
+
+impl TrafficReport {
+    /// Fails if an acknowledged write was lost, if no write was acknowledged at all or if the
+    /// ensemble was unavailable for longer than the budget.
+    pub fn verify(&self, outage_budget: Duration) -> Result<()> {
+        println!(
+            "Background traffic on [{}] for [{}] ms: [{}] acknowledged / [{}] failed writes, [{}] successful / [{}] failed reads, longest outage [{}] ms",
+            self.root,
+            self.duration.as_millis(),
+            self.acknowledged.len(),
+            self.failed_writes,
+            self.successful_reads,
+            self.failed_reads,
+            self.longest_outage.as_millis()
+        );
+
+        if !self.lost.is_empty() {
+            return Err(anyhow!("Acknowledged writes were lost: {:?}", self.lost));
+        }
+        if self.acknowledged.is_empty() {
+            return Err(anyhow!(
+                "No write to [{}] was acknowledged during [{}] ms",
+                self.root,
+                self.duration.as_millis()
+            ));
+        }
+        if self.longest_outage > outage_budget {
+            return Err(anyhow!(
+                "The ensemble was unavailable for [{}] ms which exceeds the budget of [{}] ms",
+                self.longest_outage.as_millis(),
+                outage_budget.as_millis()
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+fn run_traffic(hosts: &[String], root: String, stopped: &AtomicBool) -> Result<TrafficReport> {
+    let start = Instant::now();
+    let mut report = TrafficReport {
+        root,
+        ..TrafficReport::default()
+    };
+    let mut last_success = Instant::now();
+    let mut host_index = 0;
+    let mut client: Option<ZkClient> = None;
+
+    while !stopped.load(Ordering::SeqCst) {
+        thread::sleep(Duration::from_millis(TRAFFIC_INTERVAL_MILLIS));
+
+        if client.is_none() {
+            let host = &hosts[host_index % hosts.len()];
+            host_index += 1;
+            match ZkClient::connect(host) {
+                Ok(connected) => client = Some(connected),
+                Err(err) => {
+                    println!(
+                        "Background traffic could not connect to [{}]: {}",
+                        host, err
+                    );
+                    continue;
+                }
+            }
+        }
+        let zk = client.as_mut().unwrap();
+
+        let path = format!(
+            "{}/write-{:010}",
+            report.root,
+            report.acknowledged.len() + report.failed_writes
+        );
+        let data = Uuid::new_v4().to_string().into_bytes();
+        match zk.create(&path, &data, CreateMode::Persistent) {
+            Ok(_) => {
+                report.acknowledged.push((path.clone(), data));
+                report.longest_outage = report.longest_outage.max(last_success.elapsed());
+                last_success = Instant::now();
+            }
+            Err(err) => {
+                println!("Background traffic could not write [{}]: {}", path, err);
+                report.failed_writes += 1;
+                // the connection state is unknown, so switch to the next server
+                client = None;
+                continue;
+            }
+        }
+
+        // read back the oldest and the latest acknowledged write
+        for index in &[0, report.acknowledged.len() - 1] {
+            let (path, data) = &report.acknowledged[*index];
+            let lost = match zk.sync(path).and_then(|_| zk.get_data(path)) {
+                Ok((received, _)) => &received != data,
+                Err(err) if is_zk_error(&err, ERROR_NO_NODE) => true,
+                Err(err) => {
+                    println!("Background traffic could not read [{}]: {}", path, err);
+                    report.failed_reads += 1;
+                    client = None;
+                    break;
+                }
+            };
+
+            if !lost {
+                report.successful_reads += 1;
+            } else if !report.lost.contains(path) {
+                report.lost.push(path.clone());
+            }
+        }
+    }
+
+    report.longest_outage = report.longest_outage.max(last_success.elapsed());
+    report.duration = start.elapsed();
+    if let Some(client) = client {
+        // the session expires anyway if closing fails
+        let _ = client.close();
+    }
+    Ok(report)
+}
@@ -0,0 +1,517 @@
+use anyhow::{anyhow, Result};
+use std::fmt;
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+/// Session timeout requested in the connect handshake. The server may negotiate a different
+/// value within its min / max session timeout.
+const SESSION_TIMEOUT_MILLIS: i32 = 10_000;
+
+/// Timeout for connecting to, writing to and reading from a ZooKeeper server.
+const SOCKET_TIMEOUT: u64 = 10;
+
+/// Upper bound for a single response frame. ZooKeeper limits znode data to 1MB (jute.maxbuffer).
+const MAX_FRAME_BYTES: usize = 4 * 1024 * 1024;
+
+/// Reserved xid of watch notifications.
+const NOTIFICATION_XID: i32 = -1;
+/// Reserved xid of ping requests.
+const PING_XID: i32 = -2;
+
+/// Operation codes of the ZooKeeper wire protocol.
+const OP_CREATE: i32 = 1;
+const OP_DELETE: i32 = 2;
+const OP_EXISTS: i32 = 3;
+const OP_GET_DATA: i32 = 4;
+const OP_SET_DATA: i32 = 5;
+const OP_GET_CHILDREN: i32 = 8;
+const OP_SYNC: i32 = 9;
+const OP_PING: i32 = 11;
+const OP_CLOSE_SESSION: i32 = -11;
+
+/// The node does not exist.
+pub const ERROR_NO_NODE: i32 = -101;
+/// The expected version does not match.
+pub const ERROR_BAD_VERSION: i32 = -103;
+/// The node already exists.
+pub const ERROR_NODE_EXISTS: i32 = -110;
+/// The node has children and can not be deleted.
+pub const ERROR_NOT_EMPTY: i32 = -111;
+
+/// Error code returned by the server in the reply header of a request.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ZkError {
+    pub code: i32,
+    pub path: String,
+}
+
+impl fmt::Display for ZkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self.code {
+            -1 => "SystemError",
+            -4 => "ConnectionLoss",
+            -5 => "MarshallingError",
+            -6 => "Unimplemented",
+            -7 => "OperationTimeout",
+            -8 => "BadArguments",
+            ERROR_NO_NODE => "NoNode",
+            -102 => "NoAuth",
+            ERROR_BAD_VERSION => "BadVersion",
+            -108 => "NoChildrenForEphemerals",
+            ERROR_NODE_EXISTS => "NodeExists",
+            ERROR_NOT_EMPTY => "NotEmpty",
+            -112 => "SessionExpired",
+            -114 => "InvalidACL",
+            -115 => "AuthFailed",
+            -118 => "SessionMoved",
+            -119 => "NotReadOnly",
+            _ => "Unknown",
+        };
+        write!(
+            f,
+            "ZooKeeper error [{}] ({}) for path [{}]",
+            name, self.code, self.path
+        )
+    }
+}
+
+impl std::error::Error for ZkError {}
+
+/// Returns true if the error was caused by the server reporting the given error code.
+pub fn is_zk_error(err: &anyhow::Error, code: i32) -> bool {
+    matches!(err.downcast_ref::<ZkError>(), Some(zk_error) if zk_error.code == code)
+}
+
+/// Persistence and sequence behaviour of a created node.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CreateMode {
+    Persistent,
+    Ephemeral,
+    PersistentSequential,
+    EphemeralSequential,
+}
+
+impl CreateMode {
+    fn flags(&self) -> i32 {
+        match self {
+            CreateMode::Persistent => 0,
+            CreateMode::Ephemeral => 1,
+            CreateMode::PersistentSequential => 2,
+            CreateMode::EphemeralSequential => 3,
+        }
+    }
+}
+
+/// Metadata of a znode.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stat {
+    pub czxid: i64,
+    pub mzxid: i64,
+    pub ctime: i64,
+    pub mtime: i64,
+    pub version: i32,
+    pub cversion: i32,
+    pub aversion: i32,
+    pub ephemeral_owner: i64,
+    pub data_length: i32,
+    pub num_children: i32,
+    pub pzxid: i64,
+}
+
+/// A minimal blocking ZooKeeper client speaking the Jute based wire protocol. It only supports
+/// one outstanding request at a time and ignores watch notifications, which is all we need to
+/// verify that an ensemble stores and serves data.
+///
+/// Any failure other than an error code of the server (e.g. a read timeout in the middle of a
+/// frame) leaves the stream at an unknown position, so the client is marked as broken and all
+/// further requests fail until a new client is connected.
+pub struct ZkClient {
+    host: String,
+    stream: TcpStream,
+    xid: i32,
+    session_id: i64,
+    session_timeout: Duration,
+    /// The failure which broke the connection.
+    broken: Option<String>,
+}
+
+impl ZkClient {
+    /// Connect to the server running on host (format: <host>:<port>) and establish a new session.
+    pub fn connect(host: &str) -> Result<ZkClient> {
+        let timeout = Duration::from_secs(SOCKET_TIMEOUT);
+        let address = host
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| anyhow!("Could not resolve any address for host [{}]", host))?;
+
+        let stream = TcpStream::connect_timeout(&address, timeout)?;
+        stream.set_read_timeout(Some(timeout))?;
+        stream.set_write_timeout(Some(timeout))?;
+        stream.set_nodelay(true)?;
+
+        let mut client = ZkClient {
+            host: host.to_string(),
+            stream,
+            xid: 0,
+            session_id: 0,
+            session_timeout: Duration::from_millis(0),
+            broken: None,
+        };
+
+        // ConnectRequest
+        let mut request = JuteWriter::default();
+        request.write_i32(0); // protocol version
+        request.write_i64(0); // last zxid seen
+        request.write_i32(SESSION_TIMEOUT_MILLIS);
+        request.write_i64(0); // session id
+        request.write_buffer(&[0u8; 16]); // password
+        request.write_bool(false); // read only
+        client.write_frame(&request.0)?;
+
+        // ConnectResponse
+        let response = client.read_frame()?;
+        let mut reader = JuteReader::new(&response);
+        let _protocol_version = reader.read_i32()?;
+        let timeout = reader.read_i32()?;
+        let session_id = reader.read_i64()?;
+
+        if timeout <= 0 {
+            return Err(anyhow!(
+                "Server [{}] rejected the session (negotiated timeout [{}])",
+                host,
+                timeout
+            ));
+        }
+
+        client.session_id = session_id;
+        client.session_timeout = Duration::from_millis(timeout as u64);
+        println!(
+            "Connected to [{}] with session [0x{:x}]",
+            host, client.session_id
+        );
+
+        Ok(client)
+    }
+
+    /// The id of the established session.
+    pub fn session_id(&self) -> i64 {
+        self.session_id
+    }
+
+    /// The session timeout negotiated with the server.
+    pub fn session_timeout(&self) -> Duration {
+        self.session_timeout
+    }
+
+    /// Keep the session alive.
+    pub fn ping(&mut self) -> Result<()> {
+        self.submit(PING_XID, OP_PING, "/", &[])?;
+        Ok(())
+    }
+
+    /// Create a node with an open ACL and return the actual path (which differs from the
+    /// requested path for sequential nodes).
+    pub fn create(&mut self, path: &str, data: &[u8], mode: CreateMode) -> Result<String> {
+        let mut request = JuteWriter::default();
+        request.write_string(path);
+        request.write_buffer(data);
+        // OPEN_ACL_UNSAFE: all permissions for world:anyone
+        request.write_i32(1);
+        request.write_i32(31);
+        request.write_string("world");
+        request.write_string("anyone");
+        request.write_i32(mode.flags());
+
+        let response = self.request(OP_CREATE, path, &request.0)?;
+        JuteReader::new(&response).read_string()
+    }
+
+    /// Return the data and metadata of a node.
+    pub fn get_data(&mut self, path: &str) -> Result<(Vec<u8>, Stat)> {
+        let mut request = JuteWriter::default();
+        request.write_string(path);
+        request.write_bool(false); // watch
+
+        let response = self.request(OP_GET_DATA, path, &request.0)?;
+        let mut reader = JuteReader::new(&response);
+        let data = reader.read_buffer()?;
+        Ok((data, reader.read_stat()?))
+    }
+
+    /// Set the data of a node. If version is provided it must match the current node version.
+    pub fn set_data(&mut self, path: &str, data: &[u8], version: Option<i32>) -> Result<Stat> {
+        let mut request = JuteWriter::default();
+        request.write_string(path);
+        request.write_buffer(data);
+        request.write_i32(version.unwrap_or(-1));
+
+        let response = self.request(OP_SET_DATA, path, &request.0)?;
+        JuteReader::new(&response).read_stat()
+    }
+
+    /// Delete a node. If version is provided it must match the current node version.
+    pub fn delete(&mut self, path: &str, version: Option<i32>) -> Result<()> {
+        let mut request = JuteWriter::default();
+        request.write_string(path);
+        request.write_i32(version.unwrap_or(-1));
+
+        self.request(OP_DELETE, path, &request.0)?;
+        Ok(())
+    }
+
+    /// Return the metadata of a node or None if it does not exist.
+    pub fn exists(&mut self, path: &str) -> Result<Option<Stat>> {
+        let mut request = JuteWriter::default();
+        request.write_string(path);
+        request.write_bool(false); // watch
+
+        match self.request(OP_EXISTS, path, &request.0) {
+            Ok(response) => Ok(Some(JuteReader::new(&response).read_stat()?)),
+            Err(err) if is_zk_error(&err, ERROR_NO_NODE) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Return the names of the children of a node.
+    pub fn get_children(&mut self, path: &str) -> Result<Vec<String>> {
+        let mut request = JuteWriter::default();
+        request.write_string(path);
+        request.write_bool(false); // watch
+
+        let response = self.request(OP_GET_CHILDREN, path, &request.0)?;
+        let mut reader = JuteReader::new(&response);
+        let count = reader.read_i32()?;
+        (0..count.max(0)).map(|_| reader.read_string()).collect()
+    }
+
+    /// Make sure the connected server is up to date with the leader for the given path.
+    pub fn sync(&mut self, path: &str) -> Result<()> {
+        let mut request = JuteWriter::default();
+        request.write_string(path);
+
+        self.request(OP_SYNC, path, &request.0)?;
+        Ok(())
+    }
+
+    /// Returns true if a failure broke the connection and a new client has to be connected.
+    pub fn is_broken(&self) -> bool {
+        self.broken.is_some()
+    }
+
+    /// Close the session. Ephemeral nodes of the session are removed by the server. A broken
+    /// connection is only dropped, the server removes the session after its timeout.
+    pub fn close(mut self) -> Result<()> {
+        if self.is_broken() {
+            return Ok(());
+        }
+        let xid = self.next_xid();
+        self.submit(xid, OP_CLOSE_SESSION, "/", &[])?;
+        Ok(())
+    }
+
+    fn next_xid(&mut self) -> i32 {
+        self.xid += 1;
+        self.xid
+    }
+
+    fn request(&mut self, op: i32, path: &str, body: &[u8]) -> Result<Vec<u8>> {
+        let xid = self.next_xid();
+        self.submit(xid, op, path, body)
+    }
+
+    /// Send a request and wait for its reply. Returns the reply body after the reply header.
+    /// Marks the client as broken if the request fails for another reason than an error code of
+    /// the server.
+    fn submit(&mut self, xid: i32, op: i32, path: &str, body: &[u8]) -> Result<Vec<u8>> {
+        if let Some(failure) = &self.broken {
+            return Err(anyhow!(
+                "Connection to [{}] is broken and requires a reconnect: {}",
+                self.host,
+                failure
+            ));
+        }
+
+        let result = self.exchange(xid, op, path, body);
+        if let Err(err) = &result {
+            if err.downcast_ref::<ZkError>().is_none() {
+                self.broken = Some(format!("{:#}", err));
+            }
+        }
+        result
+    }
+
+    fn exchange(&mut self, xid: i32, op: i32, path: &str, body: &[u8]) -> Result<Vec<u8>> {
+        let mut request = JuteWriter::default();
+        request.write_i32(xid);
+        request.write_i32(op);
+        request.0.extend_from_slice(body);
+        self.write_frame(&request.0)?;
+
+        loop {
+            let response = self.read_frame()?;
+            let mut reader = JuteReader::new(&response);
+            let reply_xid = reader.read_i32()?;
+            let _zxid = reader.read_i64()?;
+            let err = reader.read_i32()?;
+
+            if reply_xid == NOTIFICATION_XID {
+                continue;
+            }
+
+            if reply_xid != xid {
+                return Err(anyhow!(
+                    "Received reply for xid [{}] from [{}] while waiting for xid [{}]",
+                    reply_xid,
+                    self.host,
+                    xid
+                ));
+            }
+
+            if err != 0 {
+                return Err(ZkError {
+                    code: err,
+                    path: path.to_string(),
+                }
+                .into());
+            }
+
+            return Ok(reader.remaining().to_vec());
+        }
+    }
+
+    fn write_frame(&mut self, payload: &[u8]) -> Result<()> {
+        self.stream
+            .write_all(&(payload.len() as i32).to_be_bytes())?;
+        self.stream.write_all(payload)?;
+        self.stream.flush()?;
+        Ok(())
+    }
+
+    fn read_frame(&mut self) -> Result<Vec<u8>> {
+        let mut length = [0u8; 4];
+        self.stream.read_exact(&mut length).map_err(|err| {
+            anyhow::Error::new(err)
+                .context(format!("Error while reading response from [{}]", self.host))
+        })?;
+
+        let length = i32::from_be_bytes(length);
+        if length < 0 || length as usize > MAX_FRAME_BYTES {
+            return Err(anyhow!(
+                "Received invalid frame length [{}] from [{}]",
+                length,
+                self.host
+            ));
+        }
+
+        let mut frame = vec![0u8; length as usize];
+        self.stream.read_exact(&mut frame).map_err(|err| {
+            anyhow::Error::new(err).context(format!(
+                "Error while reading a frame of [{}] bytes from [{}]",
+                length, self.host
+            ))
+        })?;
+        Ok(frame)
+    }
+}
+
+/// Serializes the Jute primitives (big endian numbers, length prefixed buffers and strings).
+#[derive(Default)]
+struct JuteWriter(Vec<u8>);
+
+impl JuteWriter {
+    fn write_i32(&mut self, value: i32) {
+        self.0.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn write_i64(&mut self, value: i64) {
+        self.0.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn write_bool(&mut self, value: bool) {
+        self.0.push(value as u8);
+    }
+
+    fn write_buffer(&mut self, value: &[u8]) {
+        self.write_i32(value.len() as i32);
+        self.0.extend_from_slice(value);
+    }
+
+    fn write_string(&mut self, value: &str) {
+        self.write_buffer(value.as_bytes());
+    }
+}
+
+/// Deserializes the Jute primitives written by the server.
+struct JuteReader<'a> {
+    buffer: &'a [u8],
+    position: usize,
+}
+
+impl<'a> JuteReader<'a> {
+    fn new(buffer: &'a [u8]) -> Self {
+        JuteReader {
+            buffer,
+            position: 0,
+        }
+    }
+
+    fn take(&mut self, length: usize) -> Result<&'a [u8]> {
+        if self.position + length > self.buffer.len() {
+            return Err(anyhow!(
+                "Unexpected end of response: need [{}] bytes at position [{}] of [{}]",
+                length,
+                self.position,
+                self.buffer.len()
+            ));
+        }
+        let bytes = &self.buffer[self.position..self.position + length];
+        self.position += length;
+        Ok(bytes)
+    }
+
+    fn remaining(&self) -> &'a [u8] {
+        &self.buffer[self.position..]
+    }
+
+    fn read_i32(&mut self) -> Result<i32> {
+        let mut bytes = [0u8; 4];
+        bytes.copy_from_slice(self.take(4)?);
+        Ok(i32::from_be_bytes(bytes))
+    }
+
+    fn read_i64(&mut self) -> Result<i64> {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Ok(i64::from_be_bytes(bytes))
+    }
+
+    /// A length of -1 marks a null buffer which we map to an empty one.
+    fn read_buffer(&mut self) -> Result<Vec<u8>> {
+        let length = self.read_i32()?;
+        if length < 0 {
+            return Ok(vec![]);
+        }
+        Ok(self.take(length as usize)?.to_vec())
+    }
+
+    fn read_string(&mut self) -> Result<String> {
+        Ok(String::from_utf8(self.read_buffer()?)?)
+    }
+
+    fn read_stat(&mut self) -> Result<Stat> {
+        Ok(Stat {
+            czxid: self.read_i64()?,
+            mzxid: self.read_i64()?,
+            ctime: self.read_i64()?,
+            mtime: self.read_i64()?,
+            version: self.read_i32()?,
+            cversion: self.read_i32()?,
+            aversion: self.read_i32()?,
+            ephemeral_owner: self.read_i64()?,
+            data_length: self.read_i32()?,
+            num_children: self.read_i32()?,
+            pzxid: self.read_i64()?,
+        })
+    }
+}
@@ -0,0 +1,282 @@
+use anyhow::{anyhow, Result};
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// Prefix of the properties that describe the servers of an ensemble.
+const SERVER_PREFIX: &str = "server.";
+
+pub const CLIENT_PORT: &str = "clientPort";
+pub const DATA_DIR: &str = "dataDir";
+pub const TICK_TIME: &str = "tickTime";
+pub const FOUR_LETTER_WORD_WHITELIST: &str = "4lw.commands.whitelist";
+
+/// A parsed zoo.cfg. The file uses the Java properties format, which supports comments
+/// ("#" or "!"), the separators "=", ":" or whitespace, escaped characters and line
+/// continuations via a trailing backslash.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ZooCfg {
+    /// All properties in order of appearance, including duplicate keys.
+    pub properties: Vec<(String, String)>,
+}
+
+/// Whether a server takes part in elections or only follows the ensemble.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ServerRole {
+    Participant,
+    Observer,
+}
+
+/// A "server.N=host:peerPort:electionPort[:role][;[clientAddress:]clientPort]" entry.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ServerEntry {
+    pub id: u64,
+    pub host: String,
+    pub peer_port: u16,
+    pub election_port: u16,
+    pub role: Option<ServerRole>,
+    pub client_address: Option<String>,
+    pub client_port: Option<u16>,
+}
+
+impl fmt::Display for ServerEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "server.{}={}:{}:{}",
+            self.id, self.host, self.peer_port, self.election_port
+        )
+    }
+}
+
+impl ZooCfg {
+    pub fn parse(content: &str) -> Result<ZooCfg> {
+        let mut properties = Vec::new();
+
+        for line in logical_lines(content) {
+            let (key, value) = split_key_value(&line);
+            properties.push((unescape(key)?, unescape(value)?));
+        }
+
+        Ok(ZooCfg { properties })
+    }
+
+    /// Return the value of a property. Like java.util.Properties the last occurrence wins.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.properties
+            .iter()
+            .rev()
+            .find(|(property, _)| property == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn client_port(&self) -> Result<Option<u16>> {
+        self.parse_value(CLIENT_PORT)
+    }
+
+    pub fn data_dir(&self) -> Option<&str> {
+        self.get(DATA_DIR)
+    }
+
+    pub fn tick_time(&self) -> Result<Option<u32>> {
+        self.parse_value(TICK_TIME)
+    }
+
+    /// The whitelisted four letter words. Empty if the property is not set.
+    pub fn four_letter_word_whitelist(&self) -> Vec<String> {
+        self.get(FOUR_LETTER_WORD_WHITELIST)
+            .map(|whitelist| {
+                whitelist
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|word| !word.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Parse all "server.N" entries. Fails if an entry is malformed or an id is used twice.
+    pub fn servers(&self) -> Result<Vec<ServerEntry>> {
+        let mut ids = BTreeSet::new();
+        let mut servers = Vec::new();
+
+        for (key, value) in &self.properties {
+            if let Some(id) = key.strip_prefix(SERVER_PREFIX) {
+                let server = ServerEntry::parse(id, value)?;
+                if !ids.insert(server.id) {
+                    return Err(anyhow!("Duplicate server id [{}] in zoo.cfg", server.id));
+                }
+                servers.push(server);
+            }
+        }
+
+        Ok(servers)
+    }
+
+    fn parse_value<T: std::str::FromStr>(&self, key: &str) -> Result<Option<T>> {
+        match self.get(key) {
+            None => Ok(None),
+            Some(value) => value
+                .parse()
+                .map(Some)
+                .map_err(|_| anyhow!("Invalid value [{}] for [{}] in zoo.cfg", value, key)),
+        }
+    }
+}
+
+impl ServerEntry {
+    fn parse(id: &str, value: &str) -> Result<ServerEntry> {
+        let malformed = || anyhow!("Malformed zoo.cfg entry [server.{}={}]", id, value);
+
+        let id = id.parse::<u64>().map_err(|_| malformed())?;
+        let (address, client) = match value.split_once(';') {
+            Some((address, client)) => (address, Some(client)),
+            None => (value, None),
+        };
+
+        // IPv6 addresses are enclosed in brackets
+        let (host, ports) = if let Some(rest) = address.strip_prefix('[') {
+            let (host, ports) = rest.split_once(']').ok_or_else(malformed)?;
+            (host, ports.strip_prefix(':').ok_or_else(malformed)?)
+        } else {
+            address.split_once(':').ok_or_else(malformed)?
+        };
+
+        let parts: Vec<&str> = ports.split(':').collect();
+        if host.is_empty() || parts.len() < 2 || parts.len() > 3 {
+            return Err(malformed());
+        }
+
+        let role = match parts.get(2) {
+            None => None,
+            Some(&"participant") => Some(ServerRole::Participant),
+            Some(&"observer") => Some(ServerRole::Observer),
+            Some(_) => return Err(malformed()),
+        };
+
+        let (client_address, client_port) = match client {
+            None => (None, None),
+            Some(client) => match client.rsplit_once(':') {
+                Some((address, port)) => (
+                    Some(address.to_string()),
+                    Some(parse_port(port).ok_or_else(malformed)?),
+                ),
+                None => (None, Some(parse_port(client).ok_or_else(malformed)?)),
+            },
+        };
+
+        Ok(ServerEntry {
+            id,
+            host: host.to_string(),
+            peer_port: parse_port(parts[0]).ok_or_else(malformed)?,
+            election_port: parse_port(parts[1]).ok_or_else(malformed)?,
+            role,
+            client_address,
+            client_port,
+        })
+    }
+}
+
+/// Ports must be numbers between 1 and 65535.
+fn parse_port(port: &str) -> Option<u16> {
+    port.trim().parse::<u16>().ok().filter(|port| *port != 0)
+}
+
+fn is_whitespace(c: char) -> bool {
+    c == ' ' || c == '\t' || c == '\x0c'
+}
+
+/// Join the natural lines into logical lines: leading whitespace, blank lines and comments are
+/// removed and lines ending with an odd number of backslashes are continued on the next line.
+fn logical_lines(content: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current: Option<String> = None;
+
+    for natural_line in content.lines() {
+        let trimmed = natural_line.trim_start_matches(is_whitespace);
+
+        let mut line = match current.take() {
+            Some(previous) => previous + trimmed,
+            None => {
+                if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with('!') {
+                    continue;
+                }
+                trimmed.to_string()
+            }
+        };
+
+        let trailing_backslashes = line.chars().rev().take_while(|c| *c == '\\').count();
+        if trailing_backslashes % 2 == 1 {
+            line.pop();
+            current = Some(line);
+        } else {
+            lines.push(line);
+        }
+    }
+
+    if let Some(line) = current {
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// Split a logical line at the first unescaped "=", ":" or whitespace. Whitespace around the
+/// separator is skipped. Both parts are still escaped.
+fn split_key_value(line: &str) -> (&str, &str) {
+    let mut escaped = false;
+    let mut key_end = line.len();
+
+    for (index, c) in line.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == '=' || c == ':' || is_whitespace(c) {
+            key_end = index;
+            break;
+        }
+    }
+
+    let key = &line[..key_end];
+    let mut rest = line[key_end..].trim_start_matches(is_whitespace);
+    if rest.starts_with('=') || rest.starts_with(':') {
+        rest = rest[1..].trim_start_matches(is_whitespace);
+    }
+
+    (key, rest)
+}
+
+/// Resolve the escape sequences "\t", "\n", "\r", "\f" and "\uXXXX". Any other escaped
+/// character stands for itself.
+fn unescape(escaped: &str) -> Result<String> {
+    let mut result = String::with_capacity(escaped.len());
+    let mut chars = escaped.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('t') => result.push('\t'),
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some('f') => result.push('\x0c'),
+            Some('u') => {
+                let code: String = chars.by_ref().take(4).collect();
+                let c = u32::from_str_radix(&code, 16)
+                    .ok()
+                    .filter(|_| code.len() == 4)
+                    .and_then(std::char::from_u32)
+                    .ok_or_else(|| anyhow!("Malformed \\uXXXX escape [\\u{}] in zoo.cfg", code))?;
+                result.push(c);
+            }
+            Some(other) => result.push(other),
+            None => {}
+        }
+    }
+
+    Ok(result)
+}
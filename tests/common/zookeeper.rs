@@ -1,9 +1,12 @@
-use anyhow::Result;
-use indoc::formatdoc;
+use anyhow::{anyhow, Result};
 use integration_test_commons::operator::setup::{
     TestCluster, TestClusterOptions, TestClusterTimeouts,
 };
+use semver::Version;
+use serde_json::json;
+use stackable_operator::labels::{APP_INSTANCE_LABEL, APP_NAME_LABEL};
 use stackable_zookeeper_crd::{ZookeeperCluster, ZookeeperVersion, APP_NAME};
+use std::collections::BTreeMap;
 use std::time::Duration;
 use uuid::Uuid;
 
@@ -20,6 +23,14 @@ pub fn build_test_cluster() -> TestCluster<ZookeeperCluster> {
     )
 }
 
+/// Label selector (format: key1=value1,key2=value2) matching all resources of the cluster.
+pub fn cluster_selector(cluster_name: &str) -> String {
+    format!(
+        "{}={},{}={}",
+        APP_NAME_LABEL, APP_NAME, APP_INSTANCE_LABEL, cluster_name
+    )
+}
+
 /// Used to create random cluster names. The full UUID is too long when combined in the pod
 /// names (63 characters). So we just use a slice here to avoid conflicts with the names.
 // TODO: unify naming - https://github.com/stackabletech/issues/issues/10
@@ -27,71 +38,338 @@ pub fn append_random_characters(name: &str) -> String {
     format!("{}-{}", name, Uuid::new_v4().as_fields().0)
 }
 
+/// All ZooKeeper versions supported by the operator, ordered from oldest to newest.
+pub fn all_versions() -> Vec<ZookeeperVersion> {
+    // fails to compile if the operator adds a version, as a reminder to extend the list, the
+    // versions in `version_tests!` and the transitions in `version_transition_tests!`
+    match ZookeeperVersion::v3_4_14 {
+        ZookeeperVersion::v3_4_14 | ZookeeperVersion::v3_5_8 => {}
+    }
+
+    vec![ZookeeperVersion::v3_4_14, ZookeeperVersion::v3_5_8]
+}
+
+/// Generates a test for every supported ZooKeeper version which calls the given function
+/// with the version. The tests are named after the function and the version (e.g.
+/// `test_create_cluster::v3_5_8`), so a single version can be run via `cargo test v3_5_8`.
+/// Attributes like `#[ignore]` are applied to every generated test.
+///
+/// ```ignore
+/// fn test_create_cluster(version: &ZookeeperVersion) -> Result<()> { ... }
+///
+/// version_tests!(test_create_cluster);
+/// ```
+#[macro_export]
+macro_rules! version_tests {
+    (@test [$(#[$attr:meta])*] $test:ident $version:ident) => {
+        #[test]
+        $(#[$attr])*
+        fn $version() -> anyhow::Result<()> {
+            super::$test(&ZookeeperVersion::$version)
+        }
+    };
+    (@versions $attrs:tt $test:ident; $($version:ident),+) => {
+        mod $test {
+            use stackable_zookeeper_crd::ZookeeperVersion;
+
+            $($crate::version_tests!(@test $attrs $test $version);)+
+
+            // fails to compile if the operator adds a version which is not listed below
+            #[allow(dead_code)]
+            fn all_versions_covered(version: &ZookeeperVersion) {
+                match version {
+                    $(ZookeeperVersion::$version)|+ => {}
+                }
+            }
+        }
+    };
+    ($(#[$attr:meta])* $test:ident) => {
+        $crate::version_tests!(@versions [$(#[$attr])*] $test; v3_4_14, v3_5_8);
+    };
+}
+
+/// Generates a test for every transition between two different supported ZooKeeper versions
+/// which calls the given function with the current and the new version. The tests are named
+/// after the function and the versions (e.g. `test_version_transition::v3_5_8_to_v3_4_14`), so
+/// a single transition can be run on its own.
+///
+/// ```ignore
+/// fn test_version_transition(from: &ZookeeperVersion, to: &ZookeeperVersion) -> Result<()> { ... }
+///
+/// version_transition_tests!(test_version_transition);
+/// ```
+#[macro_export]
+macro_rules! version_transition_tests {
+    (@transitions $test:ident; $($version:ident),+; $($name:ident: $from:ident => $to:ident),+) => {
+        mod $test {
+            use stackable_zookeeper_crd::ZookeeperVersion;
+
+            $(
+                #[test]
+                fn $name() -> anyhow::Result<()> {
+                    super::$test(&ZookeeperVersion::$from, &ZookeeperVersion::$to)
+                }
+            )+
+
+            // fails to compile if the operator adds a version whose transitions are not listed
+            // below
+            #[allow(dead_code)]
+            fn all_transitions_covered(from: &ZookeeperVersion, to: &ZookeeperVersion) {
+                match (from, to) {
+                    $((ZookeeperVersion::$version, ZookeeperVersion::$version))|+ => {}
+                    $((ZookeeperVersion::$from, ZookeeperVersion::$to))|+ => {}
+                }
+            }
+        }
+    };
+    ($test:ident) => {
+        $crate::version_transition_tests!(@transitions $test; v3_4_14, v3_5_8;
+            v3_4_14_to_v3_5_8: v3_4_14 => v3_5_8,
+            v3_5_8_to_v3_4_14: v3_5_8 => v3_4_14);
+    };
+}
+
+/// The direction of a change of the version in the custom resource.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum VersionTransition {
+    /// Upgrades are supported by the operator, so the servers must be restarted with the newer
+    /// version.
+    Upgrade,
+    /// The operator does not document whether it supports downgrades. The servers must either
+    /// be restarted with the older version or keep running the current version.
+    Downgrade,
+}
+
+/// The direction of the change from `from` to `to`. Fails if both versions are the same.
+pub fn version_transition(
+    from: &ZookeeperVersion,
+    to: &ZookeeperVersion,
+) -> Result<VersionTransition> {
+    let from = Version::parse(&from.to_string())?;
+    let to = Version::parse(&to.to_string())?;
+
+    if from < to {
+        Ok(VersionTransition::Upgrade)
+    } else if from > to {
+        Ok(VersionTransition::Downgrade)
+    } else {
+        Err(anyhow!("No version transition from [{}] to [{}]", from, to))
+    }
+}
+
+/// Selector of the role groups if no other labels are provided. Matches all Stackable agents.
+const DEFAULT_SELECTOR: (&str, &str) = ("kubernetes.io/arch", "stackable-linux");
+
+/// Name of the role group used by `ZkClusterBuilder::replicas`.
+pub const DEFAULT_ROLE_GROUP: &str = "default";
+
 /// This returns a ZooKeeper custom resource and the expected pod count.
 pub fn build_zk_cluster(
     name: &str,
     version: &ZookeeperVersion,
     replicas: usize,
 ) -> Result<(ZookeeperCluster, usize)> {
-    let spec = &formatdoc!(
-        "
-        apiVersion: zookeeper.stackable.tech/v1alpha1
-        kind: ZookeeperCluster
-        metadata:
-          name: {}
-        spec:
-          version: {}
-          servers:
-            roleGroups:
-              default:
-                selector:
-                  matchLabels:
-                    kubernetes.io/arch: stackable-linux
-                replicas: {}
-    ",
-        name,
-        version.to_string(),
-        replicas
-    );
-
-    Ok((serde_yaml::from_str(spec)?, replicas))
+    ZkClusterBuilder::new(name, version)
+        .replicas(replicas)
+        .build()
 }
 
-/// This returns a ZooKeeper custom resource and the expected pod count (1). We use labels
-/// for host_name and assign it to the node_ids provided by test-dev-cluster.
-/// This creates 1 ZooKeeper server with a user defined client and metrics port.
-pub fn build_zk_cluster_with_metrics_and_client_port(
+/// A role group of the ZooKeeper servers with its replicas and node selector.
+#[derive(Clone, Debug)]
+pub struct ZkRoleGroup {
+    replicas: usize,
+    selector: BTreeMap<String, String>,
+}
+
+impl ZkRoleGroup {
+    /// Create a role group which runs on all Stackable agents unless selector labels are added.
+    pub fn new(replicas: usize) -> Self {
+        ZkRoleGroup {
+            replicas,
+            selector: BTreeMap::new(),
+        }
+    }
+
+    /// Add a label the nodes of this role group have to match.
+    pub fn selector_label(mut self, key: &str, value: &str) -> Self {
+        self.selector.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    pub fn replicas(&self) -> usize {
+        self.replicas
+    }
+
+    /// The labels the nodes of this role group have to match (DEFAULT_SELECTOR if none were
+    /// added).
+    pub fn selector(&self) -> BTreeMap<String, String> {
+        if self.selector.is_empty() {
+            let mut selector = BTreeMap::new();
+            selector.insert(
+                DEFAULT_SELECTOR.0.to_string(),
+                DEFAULT_SELECTOR.1.to_string(),
+            );
+            selector
+        } else {
+            self.selector.clone()
+        }
+    }
+}
+
+/// Builds a ZooKeeper custom resource. Ports and config overrides apply to every role group.
+///
+/// ```ignore
+/// let (zookeeper_cr, expected_pod_count) = ZkClusterBuilder::new(&name, &version)
+///     .role_group("a", ZkRoleGroup::new(2).selector_label("node", "1"))
+///     .role_group("b", ZkRoleGroup::new(1).selector_label("node", "2"))
+///     .client_port(2181)
+///     .config_override("zoo.cfg", "tickTime", "3000")
+///     .build()?;
+/// ```
+#[derive(Clone, Debug)]
+pub struct ZkClusterBuilder {
+    name: String,
+    version: ZookeeperVersion,
+    role_groups: BTreeMap<String, ZkRoleGroup>,
+    client_port: Option<u16>,
+    metrics_port: Option<u16>,
+    admin_port: Option<u16>,
+    config_overrides: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl ZkClusterBuilder {
+    pub fn new(name: &str, version: &ZookeeperVersion) -> Self {
+        ZkClusterBuilder {
+            name: name.to_string(),
+            version: version.clone(),
+            role_groups: BTreeMap::new(),
+            client_port: None,
+            metrics_port: None,
+            admin_port: None,
+            config_overrides: BTreeMap::new(),
+        }
+    }
+
+    pub fn version(mut self, version: &ZookeeperVersion) -> Self {
+        self.version = version.clone();
+        self
+    }
+
+    /// Set the replicas of the DEFAULT_ROLE_GROUP running on all Stackable agents.
+    pub fn replicas(self, replicas: usize) -> Self {
+        self.role_group(DEFAULT_ROLE_GROUP, ZkRoleGroup::new(replicas))
+    }
+
+    /// Add or replace a role group.
+    pub fn role_group(mut self, name: &str, role_group: ZkRoleGroup) -> Self {
+        self.role_groups.insert(name.to_string(), role_group);
+        self
+    }
+
+    pub fn role_groups(&self) -> &BTreeMap<String, ZkRoleGroup> {
+        &self.role_groups
+    }
+
+    pub fn client_port(mut self, port: u16) -> Self {
+        self.client_port = Some(port);
+        self
+    }
+
+    pub fn metrics_port(mut self, port: u16) -> Self {
+        self.metrics_port = Some(port);
+        self
+    }
+
+    pub fn admin_port(mut self, port: u16) -> Self {
+        self.admin_port = Some(port);
+        self
+    }
+
+    /// Override a property in a config file of the servers (e.g. "zoo.cfg").
+    pub fn config_override(mut self, file: &str, key: &str, value: &str) -> Self {
+        self.config_overrides
+            .entry(file.to_string())
+            .or_default()
+            .insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// This returns the ZooKeeper custom resource and the expected pod count, which is the sum
+    /// of the replicas of all role groups.
+    pub fn build(&self) -> Result<(ZookeeperCluster, usize)> {
+        if self.role_groups.is_empty() {
+            return Err(anyhow!(
+                "ZooKeeper cluster [{}] requires at least one role group",
+                self.name
+            ));
+        }
+
+        let mut config = serde_json::Map::new();
+        for (key, port) in &[
+            ("clientPort", self.client_port),
+            ("metricsPort", self.metrics_port),
+            ("adminPort", self.admin_port),
+        ] {
+            if let Some(port) = port {
+                config.insert(key.to_string(), json!(port));
+            }
+        }
+
+        let mut role_groups = serde_json::Map::new();
+        for (name, role_group) in &self.role_groups {
+            let mut spec = json!({
+                "selector": { "matchLabels": role_group.selector() },
+                "replicas": role_group.replicas,
+            });
+            if !config.is_empty() {
+                spec["config"] = json!(config);
+            }
+            if !self.config_overrides.is_empty() {
+                spec["configOverrides"] = json!(self.config_overrides);
+            }
+
+            role_groups.insert(name.clone(), spec);
+        }
+
+        let cluster = json!({
+            "apiVersion": "zookeeper.stackable.tech/v1alpha1",
+            "kind": "ZookeeperCluster",
+            "metadata": { "name": self.name },
+            "spec": {
+                "version": self.version.to_string(),
+                "servers": { "roleGroups": role_groups },
+            },
+        });
+
+        let expected_pod_count = self
+            .role_groups
+            .values()
+            .map(|role_group| role_group.replicas)
+            .sum();
+
+        Ok((serde_json::from_value(cluster)?, expected_pod_count))
+    }
+}
+
+/// Node label set on the test nodes (node=1, node=2, ...) to pin role groups to nodes.
+pub const NODE_LABEL: &str = "node";
+
+/// This returns a builder with one role group per provided node label value ("node-<value>"),
+/// each pinned to the nodes with the label node=<value> and running the given replicas.
+/// Several ZooKeeper servers can not share a node, so replicas should not exceed the number of
+/// nodes with the label.
+pub fn zk_cluster_with_node_role_groups(
     name: &str,
     version: &ZookeeperVersion,
-    replicas: usize,
-    client_port: u16,
-    metrics_port: u16,
-) -> Result<(ZookeeperCluster, usize)> {
-    let spec = &formatdoc!(
-        "
-        apiVersion: zookeeper.stackable.tech/v1alpha1
-        kind: ZookeeperCluster
-        metadata:
-          name: {}
-        spec:
-          version: {}
-          servers:
-            roleGroups:
-              default:
-                selector:
-                  matchLabels:
-                    kubernetes.io/arch: stackable-linux
-                replicas: {}
-                config:
-                  clientPort: {}
-                  metricsPort: {}
-    ",
-        name,
-        version.to_string(),
-        replicas,
-        client_port,
-        metrics_port,
-    );
-
-    Ok((serde_yaml::from_str(spec)?, replicas))
+    nodes_and_replicas: &[(&str, usize)],
+) -> ZkClusterBuilder {
+    nodes_and_replicas.iter().fold(
+        ZkClusterBuilder::new(name, version),
+        |builder, (node, replicas)| {
+            builder.role_group(
+                &format!("node-{}", node),
+                ZkRoleGroup::new(*replicas).selector_label(NODE_LABEL, node),
+            )
+        },
+    )
 }
@@ -1,50 +1,46 @@
 pub mod common;
 
-use crate::common::checks::custom_checks;
-
+use crate::common::checks::{check_replication, check_znode_round_trip, custom_checks};
+use crate::common::diagnostics::with_diagnostics;
+use crate::common::four_letter_commands::{ServerAccess, DEFAULT_CLIENT_PORT};
 use crate::common::zookeeper::append_random_characters;
 use anyhow::Result;
 use common::zookeeper::{build_test_cluster, build_zk_cluster};
 use stackable_zookeeper_crd::ZookeeperVersion;
 
-#[test]
-fn test_create_cluster_3_4_14() -> Result<()> {
-    let name = append_random_characters("simple");
-    let version = ZookeeperVersion::v3_4_14;
-    let mut cluster = build_test_cluster();
-
-    let (zookeeper_cr, expected_pod_count) = build_zk_cluster(&name, &version, 1)?;
-    cluster.create_or_update(&zookeeper_cr, expected_pod_count)?;
-    let created_pods = cluster.list_pods();
+version_tests!(test_create_standalone_server);
+version_tests!(test_create_ensemble);
 
-    custom_checks(
-        &cluster.client,
-        created_pods.as_slice(),
-        &version,
-        2181,
-        expected_pod_count,
-    )?;
+fn test_create_standalone_server(version: &ZookeeperVersion) -> Result<()> {
+    create_cluster(
+        &format!("test_create_standalone_server_{}", version),
+        version,
+        1,
+    )
+}
 
-    Ok(())
+fn test_create_ensemble(version: &ZookeeperVersion) -> Result<()> {
+    create_cluster(&format!("test_create_ensemble_{}", version), version, 3)
 }
 
-#[test]
-fn test_create_cluster_3_5_8() -> Result<()> {
+fn create_cluster(test_name: &str, version: &ZookeeperVersion, replicas: usize) -> Result<()> {
     let name = append_random_characters("simple");
-    let version = ZookeeperVersion::v3_5_8;
     let mut cluster = build_test_cluster();
 
-    let (zookeeper_cr, expected_pod_count) = build_zk_cluster(&name, &version, 3)?;
-    cluster.create_or_update(&zookeeper_cr, expected_pod_count)?;
-    let created_pods = cluster.list_pods();
-
-    custom_checks(
-        &cluster.client,
-        created_pods.as_slice(),
-        &version,
-        8080,
-        expected_pod_count,
-    )?;
-
-    Ok(())
+    with_diagnostics(test_name, &mut cluster, &name, |cluster| {
+        let (zookeeper_cr, expected_pod_count) = build_zk_cluster(&name, version, replicas)?;
+        cluster.create_or_update(&zookeeper_cr, expected_pod_count)?;
+        let created_pods = cluster.list_pods();
+
+        custom_checks(
+            &cluster.client,
+            created_pods.as_slice(),
+            &ServerAccess::new(version)?,
+            expected_pod_count,
+        )?;
+        check_znode_round_trip(created_pods.as_slice(), DEFAULT_CLIENT_PORT)?;
+        check_replication(created_pods.as_slice(), DEFAULT_CLIENT_PORT)?;
+
+        Ok(())
+    })
 }
@@ -0,0 +1,135 @@
+pub mod common;
+
+use crate::common::diagnostics::{
+    diagnostics_dir, dump_diagnostics, panic_message, record_response, recorded_responses,
+    run_with_dump,
+};
+use crate::common::kube_api::{FakeKubeApi, KubeApi};
+use anyhow::{anyhow, Result};
+use std::fs;
+use std::panic;
+use std::thread;
+
+#[test]
+fn test_dump_diagnostics() -> Result<()> {
+    let mut api = FakeKubeApi::from_fixture("zookeeper_3_servers")?;
+    api.set_logs(
+        "simple-server-default-node-2",
+        "zookeeper",
+        &[
+            "INFO Starting server",
+            "ERROR Unable to load database on disk",
+        ],
+    );
+    record_response(
+        "mntr node-1:2181",
+        &Ok("zk_server_state\tleader\n".to_string()),
+    );
+    record_response(
+        "http://node-2:8080/commands/monitor",
+        &Err(anyhow!("connection refused")),
+    );
+
+    let dir = diagnostics_dir("test_dump_diagnostics");
+    dump_diagnostics(&dir, &api, "simple", "custom checks failed")?;
+
+    assert_eq!(
+        "custom checks failed",
+        fs::read_to_string(dir.join("failure.txt"))?
+    );
+
+    let cluster = fs::read_to_string(dir.join("cluster.yaml"))?;
+    assert!(cluster.contains("currentVersion"));
+
+    // the dumped resources can be loaded again to reproduce the failure offline
+    let dumped = FakeKubeApi::from_yaml(&fs::read_to_string(dir.join("pods.yaml"))?)?;
+    assert_eq!(3, dumped.list_pods("").len());
+    let dumped = FakeKubeApi::from_yaml(&fs::read_to_string(dir.join("configmaps.yaml"))?)?;
+    assert_eq!(6, dumped.list_config_maps("").len());
+
+    let events = fs::read_to_string(dir.join("events.yaml"))?;
+    assert!(events.contains("Created pod simple-server-default-node-1"));
+    assert!(events.contains("Successfully assigned"));
+
+    let logs = fs::read_to_string(dir.join("logs").join("simple-server-default-node-2.log"))?;
+    assert!(logs.contains("Unable to load database on disk"));
+    assert!(dir
+        .join("logs")
+        .join("simple-server-default-node-1.log")
+        .exists());
+
+    let responses = fs::read_to_string(dir.join("responses.txt"))?;
+    assert!(responses.contains("zk_server_state\tleader"));
+    assert!(responses.contains("ERROR: connection refused"));
+
+    Ok(())
+}
+
+#[test]
+fn test_dump_diagnostics_without_cluster() -> Result<()> {
+    let api = FakeKubeApi::default();
+
+    let dir = diagnostics_dir("test_dump_diagnostics_without_cluster");
+    dump_diagnostics(&dir, &api, "missing", "cluster was not created")?;
+
+    assert!(fs::read_to_string(dir.join("cluster.yaml"))?.contains("not found"));
+    assert!(fs::read_to_string(dir.join("pods.yaml"))?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_responses_are_recorded_per_test_thread() {
+    record_response("ruok node-1:2181", &Ok("imok".to_string()));
+
+    let other_test = thread::spawn(|| {
+        record_response("ruok node-9:2181", &Ok("imok".to_string()));
+        recorded_responses()
+    })
+    .join()
+    .unwrap();
+
+    let responses = recorded_responses();
+    assert!(responses.iter().any(|entry| entry.contains("node-1")));
+    assert!(!responses.iter().any(|entry| entry.contains("node-9")));
+    assert_eq!(1, other_test.len());
+}
+
+#[test]
+fn test_failing_dump_keeps_the_original_error() {
+    let err = run_with_dump(
+        &mut (),
+        |_| Err(anyhow!("leader not found")),
+        |_, failure| {
+            assert!(failure.contains("leader not found"));
+            panic!("dump broke")
+        },
+    )
+    .unwrap_err();
+    assert_eq!("leader not found", err.to_string());
+
+    let err = run_with_dump(
+        &mut (),
+        |_| Err(anyhow!("leader not found")),
+        |_, _| Err(anyhow!("disk full")),
+    )
+    .unwrap_err();
+    assert_eq!("leader not found", err.to_string());
+}
+
+#[test]
+fn test_failing_dump_keeps_the_original_panic() {
+    let panic = panic::catch_unwind(|| {
+        run_with_dump(
+            &mut (),
+            |_| panic!("custom checks panicked"),
+            |_, _| panic!("dump broke"),
+        )
+    })
+    .unwrap_err();
+    assert_eq!("custom checks panicked", panic_message(&*panic));
+}
+
+#[test]
+fn test_successful_test_is_not_dumped() -> Result<()> {
+    run_with_dump(&mut (), |_| Ok(()), |_, _| panic!("must not be called"))
+}
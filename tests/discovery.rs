@@ -0,0 +1,109 @@
+pub mod common;
+
+use crate::common::checks::verify_connect_string_hosts;
+use crate::common::discovery::{connect_string_for_pods, ConnectString};
+use crate::common::kube_api::{FakeKubeApi, KubeApi};
+use anyhow::Result;
+
+const FIXTURE: &str = "zookeeper_3_servers";
+const CLUSTER_SELECTOR: &str = "app.kubernetes.io/name=zookeeper,app.kubernetes.io/instance=simple";
+
+#[test]
+fn test_parse_connect_string() -> Result<()> {
+    let connect_string = ConnectString::parse("node-1:2181,node-2:2182,node-3/kafka/cluster-a")?;
+
+    assert_eq!(
+        vec![
+            ("node-1".to_string(), 2181),
+            ("node-2".to_string(), 2182),
+            ("node-3".to_string(), 2181)
+        ],
+        connect_string.hosts
+    );
+    assert_eq!(Some("/kafka/cluster-a".to_string()), connect_string.chroot);
+    assert_eq!(
+        "node-1:2181,node-2:2182,node-3:2181/kafka/cluster-a",
+        connect_string.to_string()
+    );
+    Ok(())
+}
+
+#[test]
+fn test_parse_connect_string_with_ipv6_hosts() -> Result<()> {
+    let connect_string = ConnectString::parse(" [::1]:2181, [fe80::1] /")?;
+
+    assert_eq!(
+        vec![("::1".to_string(), 2181), ("fe80::1".to_string(), 2181)],
+        connect_string.hosts
+    );
+    assert_eq!(None, connect_string.chroot);
+    assert_eq!(
+        vec!["[::1]:2181", "[fe80::1]:2181"],
+        connect_string.addresses()
+    );
+    Ok(())
+}
+
+#[test]
+fn test_parse_invalid_connect_strings() {
+    for invalid in &[
+        "",
+        "node-1:2181,,node-2:2181",
+        "node-1:",
+        "node-1:0",
+        "node-1:65536",
+        "node-1:client",
+        ":2181",
+        "::1:2181",
+        "[::1:2181",
+        "[::1]2181",
+        "node-1:2181/chroot/",
+        "node-1:2181//chroot",
+    ] {
+        assert!(
+            ConnectString::parse(invalid).is_err(),
+            "[{}] should be invalid",
+            invalid
+        );
+    }
+}
+
+#[test]
+fn test_connect_string_for_pods() -> Result<()> {
+    let api = FakeKubeApi::from_fixture(FIXTURE)?;
+    let pods = api.list_pods(CLUSTER_SELECTOR);
+
+    let connect_string = connect_string_for_pods(&pods, 2181, None)?;
+    assert_eq!(None, connect_string.chroot);
+    verify_connect_string_hosts(&connect_string, &pods, 2181)?;
+
+    let with_chroot = connect_string_for_pods(&pods, 2181, Some("/simple"))?;
+    assert_eq!(Some("/simple".to_string()), with_chroot.chroot);
+    assert_eq!(with_chroot, ConnectString::parse(&with_chroot.to_string())?);
+    verify_connect_string_hosts(&with_chroot, &pods, 2181)?;
+
+    assert!(connect_string_for_pods(&pods, 2181, Some("/simple/")).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_verify_connect_string_hosts_detects_mismatches() -> Result<()> {
+    let api = FakeKubeApi::from_fixture(FIXTURE)?;
+    let pods = api.list_pods(CLUSTER_SELECTOR);
+
+    for mismatch in &[
+        "node-1:2181,node-2:2181",
+        "node-1:2181,node-2:2181,node-3:2181,node-4:2181",
+        "node-1:2181,node-2:2181,node-2:2181",
+        "node-1:2181,node-2:2181,node-3:2182",
+        "10.0.0.1:2181,10.0.0.2:2181,10.0.0.3:2181",
+    ] {
+        let connect_string = ConnectString::parse(mismatch)?;
+        assert!(
+            verify_connect_string_hosts(&connect_string, &pods, 2181).is_err(),
+            "[{}] should not match the pods",
+            mismatch
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,88 @@
+pub mod common;
+
+use crate::common::four_letter_commands::{Mntr, ServerState};
+use anyhow::Result;
+use indoc::indoc;
+
+#[test]
+fn test_parse_mntr_3_4_14() -> Result<()> {
+    let response = indoc!(
+        "
+        zk_version\t3.4.14-4c25d480e66aadd371de8bd2fd8da255ac140bcf, built on 03/06/2019 16:18 GMT
+        zk_avg_latency\t1
+        zk_max_latency\t12
+        zk_min_latency\t0
+        zk_packets_received\t42
+        zk_packets_sent\t41
+        zk_num_alive_connections\t1
+        zk_outstanding_requests\t0
+        zk_server_state\tleader
+        zk_znode_count\t4
+        zk_watch_count\t0
+        zk_ephemerals_count\t0
+        zk_approximate_data_size\t27
+        zk_open_file_descriptor_count\t32
+        zk_max_file_descriptor_count\t1048576
+        zk_fsync_threshold_exceed_count\t0
+        zk_followers\t2
+        zk_synced_followers\t2
+        zk_pending_syncs\t0
+        "
+    );
+
+    let mntr = Mntr::from_4lw_response(response)?;
+
+    assert_eq!(ServerState::Leader, mntr.server_state);
+    assert_eq!(4, mntr.znode_count);
+    assert_eq!(12, mntr.max_latency);
+    assert_eq!(Some(2), mntr.followers);
+    assert_eq!(Some(2), mntr.synced_followers);
+    assert_eq!(
+        Some(&"0".to_string()),
+        mntr.values.get("fsync_threshold_exceed_count")
+    );
+    Ok(())
+}
+
+#[test]
+fn test_parse_mntr_3_5_8() -> Result<()> {
+    let response = indoc!(
+        r#"
+        {
+          "version" : "3.5.8-f439ca583e70862c3068a1f2a7d4d068eec33315, built on 05/04/2020 15:07 GMT",
+          "avg_latency" : 0,
+          "max_latency" : 0,
+          "min_latency" : 0,
+          "packets_received" : 1,
+          "packets_sent" : 0,
+          "num_alive_connections" : 0,
+          "outstanding_requests" : 0,
+          "server_state" : "follower",
+          "znode_count" : 5,
+          "watch_count" : 0,
+          "ephemerals_count" : 0,
+          "approximate_data_size" : 44,
+          "open_file_descriptor_count" : 67,
+          "max_file_descriptor_count" : 1048576,
+          "last_client_response_size" : -1,
+          "command" : "monitor",
+          "error" : null
+        }
+        "#
+    );
+
+    let mntr = Mntr::from_admin_server_response(response)?;
+
+    assert_eq!(ServerState::Follower, mntr.server_state);
+    assert_eq!(5, mntr.znode_count);
+    assert_eq!(None, mntr.followers);
+    assert_eq!(Some(67), mntr.open_file_descriptor_count);
+    Ok(())
+}
+
+#[test]
+fn test_parse_mntr_admin_server_error() {
+    let response = r#"{ "command" : "monitor", "error" : "This ZooKeeper instance is not currently serving requests" }"#;
+
+    assert!(Mntr::from_admin_server_response(response).is_err());
+}
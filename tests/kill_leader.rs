@@ -0,0 +1,103 @@
+pub mod common;
+
+use crate::common::checks::{
+    check_ensemble_size, check_znodes, custom_checks, verify_pod_replaced, wait_for_leader,
+    write_znodes,
+};
+use crate::common::diagnostics::with_diagnostics;
+use crate::common::four_letter_commands::{ServerAccess, DEFAULT_CLIENT_PORT};
+use crate::common::retry::RetryPolicy;
+use crate::common::zookeeper::{append_random_characters, build_test_cluster, build_zk_cluster};
+use anyhow::{anyhow, Result};
+use stackable_zookeeper_crd::ZookeeperVersion;
+use std::time::{Duration, Instant};
+
+/// Number of servers of the ensemble. The remaining servers keep the quorum after the leader
+/// was killed.
+const REPLICAS: usize = 3;
+
+/// Number of znodes written before the leader is killed which must be readable afterwards.
+const PRE_KILL_ZNODES: usize = 20;
+
+/// Timeout in seconds until a leader is elected, before and after the leader was killed.
+const LEADER_ELECTION_TIMEOUT: u64 = 60;
+
+/// Timeout in seconds until the operator replaced the killed pod and it is ready.
+const POD_REPLACEMENT_TIMEOUT: u64 = 300;
+
+/// Interval between two checks whether the killed pod was replaced.
+const POD_REPLACEMENT_POLL_INTERVAL_MILLIS: u64 = 1000;
+
+version_tests!(test_kill_leader);
+
+fn test_kill_leader(version: &ZookeeperVersion) -> Result<()> {
+    let name = append_random_characters("simple");
+    let access = ServerAccess::new(version)?;
+    let mut cluster = build_test_cluster();
+
+    with_diagnostics(
+        &format!("test_kill_leader_{}", version),
+        &mut cluster,
+        &name,
+        |cluster| {
+            let (zookeeper_cr, expected_pod_count) = build_zk_cluster(&name, version, REPLICAS)?;
+            cluster.create_or_update(&zookeeper_cr, expected_pod_count)?;
+            let pods = cluster.list_pods();
+
+            custom_checks(
+                &cluster.client,
+                pods.as_slice(),
+                &access,
+                expected_pod_count,
+            )?;
+            let znodes = write_znodes(pods.as_slice(), DEFAULT_CLIENT_PORT, PRE_KILL_ZNODES)?;
+
+            let election_timeout = Duration::from_secs(LEADER_ELECTION_TIMEOUT);
+            let leader_name = wait_for_leader(pods.as_slice(), &access, election_timeout)?;
+            let (leader, remaining_pods): (Vec<_>, Vec<_>) = pods
+                .into_iter()
+                .partition(|pod| pod.metadata.name.as_ref() == Some(&leader_name));
+            let leader = leader
+                .into_iter()
+                .next()
+                .ok_or_else(|| anyhow!("Leader [{}] is not a pod of the cluster", leader_name))?;
+
+            println!("Deleting leader [{}] of cluster [{}]", leader_name, name);
+            let killed = Instant::now();
+            cluster.client.delete(leader.clone());
+
+            let new_leader_name =
+                wait_for_leader(remaining_pods.as_slice(), &access, election_timeout)?;
+            let election_time = killed.elapsed();
+
+            RetryPolicy::new(Duration::from_secs(POD_REPLACEMENT_TIMEOUT))
+                .initial_delay(Duration::from_millis(POD_REPLACEMENT_POLL_INTERVAL_MILLIS))
+                .backoff_factor(1.0)
+                .retry(&format!("replacement of [{}]", leader_name), || {
+                    verify_pod_replaced(&leader, &cluster.list_pods(), expected_pod_count)
+                })?;
+            let replacement_time = killed.elapsed();
+
+            println!(
+                "Killed leader [{}]: [{}] elected after [{}] ms, pod replaced after [{}] ms",
+                leader_name,
+                new_leader_name,
+                election_time.as_millis(),
+                replacement_time.as_millis()
+            );
+
+            // the replaced server must rejoin the ensemble and serve the data written before
+            let pods = cluster.list_pods();
+            custom_checks(
+                &cluster.client,
+                pods.as_slice(),
+                &access,
+                expected_pod_count,
+            )?;
+            check_ensemble_size(pods.as_slice(), &access)?;
+            check_znodes(pods.as_slice(), DEFAULT_CLIENT_PORT, &znodes)?;
+
+            Ok(())
+        },
+    )
+}
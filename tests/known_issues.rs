@@ -0,0 +1,84 @@
+pub mod common;
+
+use crate::common::known_issues::{expect_failure, KnownIssue, ENV_IGNORE_KNOWN_ISSUES};
+use anyhow::{anyhow, Result};
+use std::env;
+use std::sync::{Mutex, MutexGuard};
+
+/// `expect_failure` reads an environment variable, so tests modifying it must not run in
+/// parallel.
+static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+const ISSUE: KnownIssue = KnownIssue {
+    url: "https://github.com/stackabletech/zookeeper-operator/issues/1",
+    summary: "test issue",
+    symptom: "config map not updated",
+};
+
+fn lock_env() -> MutexGuard<'static, ()> {
+    ENV_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[test]
+fn test_expected_failure_is_accepted() -> Result<()> {
+    let _env = lock_env();
+    let mut ran = false;
+
+    expect_failure(&ISSUE, || {
+        ran = true;
+        Err(anyhow!("config map not updated"))
+    })?;
+
+    assert!(ran);
+    Ok(())
+}
+
+#[test]
+fn test_expected_panic_is_accepted() -> Result<()> {
+    let _env = lock_env();
+
+    expect_failure(&ISSUE, || panic!("config map not updated"))
+}
+
+#[test]
+fn test_failure_without_symptom_fails() {
+    let _env = lock_env();
+
+    let err = expect_failure(&ISSUE, || Err(anyhow!("connection refused"))).unwrap_err();
+    assert!(err.to_string().contains(ISSUE.url));
+
+    let err = expect_failure(&ISSUE, || panic!("index out of bounds")).unwrap_err();
+    assert!(err.to_string().contains("index out of bounds"));
+}
+
+#[test]
+fn test_symptom_in_error_context_is_accepted() -> Result<()> {
+    let _env = lock_env();
+
+    expect_failure(&ISSUE, || {
+        Err(anyhow!("server.3 missing").context("config map not updated for pod [zk-3]"))
+    })
+}
+
+#[test]
+fn test_unexpected_success_fails() {
+    let _env = lock_env();
+
+    let err = expect_failure(&ISSUE, || Ok(())).unwrap_err();
+    assert!(err.to_string().contains(ISSUE.url));
+}
+
+#[test]
+fn test_ignore_known_issues() {
+    let _env = lock_env();
+    env::set_var(ENV_IGNORE_KNOWN_ISSUES, "true");
+
+    let failure = expect_failure(&ISSUE, || Err(anyhow!("config map not updated")));
+    let success = expect_failure(&ISSUE, || Ok(()));
+
+    env::remove_var(ENV_IGNORE_KNOWN_ISSUES);
+    assert!(failure.is_err());
+    assert!(success.is_ok());
+}
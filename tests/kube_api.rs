@@ -0,0 +1,287 @@
+pub mod common;
+
+use crate::common::checks::{
+    check_config_map, check_container_ports, check_endpoints, check_role_group_placement,
+    check_services,
+};
+use crate::common::four_letter_commands::ServerAccess;
+use crate::common::kube_api::{FakeKubeApi, KubeApi};
+use crate::common::zookeeper::ZkRoleGroup;
+use anyhow::Result;
+use indoc::indoc;
+use integration_test_commons::test::prelude::Pod;
+use stackable_zookeeper_crd::ZookeeperVersion;
+use std::collections::BTreeMap;
+
+const FIXTURE: &str = "zookeeper_3_servers";
+const CLUSTER_SELECTOR: &str = "app.kubernetes.io/name=zookeeper,app.kubernetes.io/instance=simple";
+
+fn check_all_config_maps(api: &FakeKubeApi, pods: &[Pod], expected: usize) -> Result<()> {
+    let access = ServerAccess::new(&ZookeeperVersion::v3_5_8)?;
+    for pod in pods {
+        check_config_map(api, pod, pods, &access, expected)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_fake_kube_api_from_fixture() -> Result<()> {
+    let api = FakeKubeApi::from_fixture(FIXTURE)?;
+    let pods = api.list_pods(CLUSTER_SELECTOR);
+
+    assert_eq!(3, pods.len());
+    assert_eq!(0, api.list_pods("app.kubernetes.io/instance=other").len());
+    assert_eq!(1, api.list_services(CLUSTER_SELECTOR).len());
+    assert!(api.find_endpoints("simple").is_some());
+    assert_eq!(1, api.get_events(&pods[0]).len());
+    assert!(api.get_events(&pods[1]).is_empty());
+    assert!(api
+        .find_config_map("simple-server-default-node-1-config")
+        .is_some());
+    assert!(api.find_node("node-1").is_some());
+    Ok(())
+}
+
+#[test]
+fn test_check_config_map() -> Result<()> {
+    let api = FakeKubeApi::from_fixture(FIXTURE)?;
+    let pods = api.list_pods(CLUSTER_SELECTOR);
+
+    check_all_config_maps(&api, &pods, 3)
+}
+
+#[test]
+fn test_check_config_map_detects_stale_servers_after_scale_down() -> Result<()> {
+    // scaled down to one server but the zoo.cfg still lists all three servers
+    let api = FakeKubeApi::from_fixture(FIXTURE)?;
+    let pods: Vec<Pod> = api
+        .list_pods(CLUSTER_SELECTOR)
+        .into_iter()
+        .filter(|pod| pod.spec.as_ref().unwrap().node_name.as_deref() == Some("node-1"))
+        .collect();
+
+    assert!(check_all_config_maps(&api, &pods, 1).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_check_config_map_detects_wrong_myid() -> Result<()> {
+    let mut api = FakeKubeApi::from_fixture(FIXTURE)?;
+    api.apply_yaml(indoc!(
+        r#"
+        apiVersion: v1
+        kind: ConfigMap
+        metadata:
+          name: simple-server-default-node-2-id
+          labels:
+            configmap.stackable.tech/type: id
+        data:
+          myid: "3"
+        "#
+    ))?;
+    let pods = api.list_pods(CLUSTER_SELECTOR);
+
+    assert!(check_all_config_maps(&api, &pods, 3).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_check_config_map_detects_missing_data_config_map() -> Result<()> {
+    let mut api = FakeKubeApi::from_fixture(FIXTURE)?;
+    api.apply_yaml(indoc!(
+        r#"
+        apiVersion: v1
+        kind: ConfigMap
+        metadata:
+          name: simple-server-default-node-3-config
+        data:
+          zoo.cfg: ""
+        "#
+    ))?;
+    let pods = api.list_pods(CLUSTER_SELECTOR);
+
+    assert!(check_all_config_maps(&api, &pods, 3).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_check_container_ports() -> Result<()> {
+    let api = FakeKubeApi::from_fixture(FIXTURE)?;
+
+    for pod in api.list_pods(CLUSTER_SELECTOR) {
+        check_container_ports(
+            &pod,
+            &[("client", 2181), ("metrics", 9505), ("admin", 8080)],
+            "zookeeper",
+        )?;
+        assert!(check_container_ports(&pod, &[("metrics", 9506)], "zookeeper").is_err());
+        assert!(check_container_ports(&pod, &[("client", 2181)], "unknown").is_err());
+    }
+    Ok(())
+}
+
+#[test]
+fn test_check_role_group_placement() -> Result<()> {
+    let api = FakeKubeApi::from_fixture(FIXTURE)?;
+    let pods = api.list_pods(CLUSTER_SELECTOR);
+
+    let mut role_groups = BTreeMap::new();
+    role_groups.insert("default".to_string(), ZkRoleGroup::new(3));
+    check_role_group_placement(&api, &pods, &role_groups)?;
+
+    role_groups.insert(
+        "default".to_string(),
+        ZkRoleGroup::new(3).selector_label("node", "1"),
+    );
+    assert!(check_role_group_placement(&api, &pods, &role_groups).is_err());
+    Ok(())
+}
+
+const CONTAINER_PORTS: &[(&str, u16)] = &[("client", 2181), ("metrics", 9505), ("admin", 8080)];
+
+#[test]
+fn test_check_services() -> Result<()> {
+    let api = FakeKubeApi::from_fixture(FIXTURE)?;
+    let pods = api.list_pods(CLUSTER_SELECTOR);
+
+    check_services(&api, "simple", &pods, CONTAINER_PORTS, "zookeeper")?;
+
+    // the port is set on the container but not exposed by the service
+    assert!(check_services(&api, "simple", &pods, &[("jmx", 9010)], "zookeeper").is_err());
+    // no service for another cluster
+    assert!(check_services(&api, "other", &pods, CONTAINER_PORTS, "zookeeper").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_check_services_detects_foreign_pods() -> Result<()> {
+    let mut api = FakeKubeApi::from_fixture(FIXTURE)?;
+    api.apply_yaml(indoc!(
+        r#"
+        apiVersion: v1
+        kind: Service
+        metadata:
+          name: simple
+          labels:
+            app.kubernetes.io/name: zookeeper
+            app.kubernetes.io/instance: simple
+        spec:
+          selector:
+            app.kubernetes.io/name: zookeeper
+          ports:
+            - name: client
+              port: 2181
+        ---
+        apiVersion: v1
+        kind: Pod
+        metadata:
+          name: other-server-default-node-1
+          labels:
+            app.kubernetes.io/name: zookeeper
+            app.kubernetes.io/instance: other
+        spec:
+          nodeName: node-1
+          containers:
+            - name: zookeeper
+              ports:
+                - name: client
+                  containerPort: 2181
+        "#
+    ))?;
+    let pods = api.list_pods(CLUSTER_SELECTOR);
+
+    assert!(check_services(&api, "simple", &pods, &[("client", 2181)], "zookeeper").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_check_services_detects_wrong_target_port() -> Result<()> {
+    let mut api = FakeKubeApi::from_fixture(FIXTURE)?;
+    api.apply_yaml(indoc!(
+        r#"
+        apiVersion: v1
+        kind: Service
+        metadata:
+          name: simple
+          labels:
+            app.kubernetes.io/name: zookeeper
+            app.kubernetes.io/instance: simple
+        spec:
+          selector:
+            app.kubernetes.io/name: zookeeper
+            app.kubernetes.io/instance: simple
+          ports:
+            - name: client
+              port: 2181
+              targetPort: 2182
+        "#
+    ))?;
+    let pods = api.list_pods(CLUSTER_SELECTOR);
+
+    assert!(check_services(&api, "simple", &pods, &[("client", 2181)], "zookeeper").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_check_endpoints() -> Result<()> {
+    let mut api = FakeKubeApi::from_fixture(FIXTURE)?;
+    let service = api.list_services(CLUSTER_SELECTOR).remove(0);
+    api.apply_yaml(indoc!(
+        r#"
+        apiVersion: v1
+        kind: Endpoints
+        metadata:
+          name: simple
+        subsets:
+          - addresses:
+              - ip: 10.0.0.1
+                targetRef:
+                  kind: Pod
+                  name: simple-server-default-node-1
+              - ip: 10.0.0.2
+                targetRef:
+                  kind: Pod
+                  name: simple-server-default-node-2
+            notReadyAddresses:
+              - ip: 10.0.0.3
+                targetRef:
+                  kind: Pod
+                  name: simple-server-default-node-3
+            ports:
+              - name: client
+                port: 2181
+              - name: metrics
+                port: 9505
+              - name: admin
+                port: 8080
+        "#
+    ))?;
+
+    // the third pod is ready but not in the endpoints
+    let pods = api.list_pods(CLUSTER_SELECTOR);
+    assert!(check_endpoints(&api, &service, &pods).is_err());
+
+    // pods which are not ready are not required
+    api.apply_yaml(indoc!(
+        r#"
+        apiVersion: v1
+        kind: Pod
+        metadata:
+          name: simple-server-default-node-3
+          labels:
+            app.kubernetes.io/name: zookeeper
+            app.kubernetes.io/instance: simple
+        spec:
+          nodeName: node-3
+          containers:
+            - name: zookeeper
+        status:
+          phase: Running
+          conditions:
+            - type: Ready
+              status: "False"
+        "#
+    ))?;
+    let pods = api.list_pods(CLUSTER_SELECTOR);
+    check_endpoints(&api, &service, &pods)
+}
@@ -0,0 +1,182 @@
+pub mod common;
+
+use crate::common::capabilities::MetricsFormat;
+use crate::common::checks::{
+    check_metrics_endpoint, compare_metrics_with_mntr, verify_metrics_match_mntr,
+};
+use crate::common::four_letter_commands::{send_mntr, ServerState};
+use crate::common::metrics::ZkMetrics;
+use crate::common::mock_server::MockZkServer;
+use crate::common::prometheus::Metrics;
+use anyhow::Result;
+use indoc::indoc;
+use stackable_zookeeper_crd::ZookeeperVersion;
+
+#[test]
+fn test_check_metrics_endpoint_of_ensemble() -> Result<()> {
+    let servers = MockZkServer::start_ensemble(&[ServerState::Leader, ServerState::Follower])?;
+    servers[1].update(|state| {
+        state.znode_count = 42;
+        state.outstanding_requests = 2;
+    });
+
+    for version in &[ZookeeperVersion::v3_4_14, ZookeeperVersion::v3_5_8] {
+        let leader = check_metrics_endpoint(&servers[0].address(), version)?;
+        assert_eq!(Some(ServerState::Leader), leader.server_state);
+
+        let follower = check_metrics_endpoint(&servers[1].address(), version)?;
+        assert_eq!(
+            ZkMetrics {
+                server_state: Some(ServerState::Follower),
+                znode_count: 42,
+                alive_connections: 1,
+                outstanding_requests: 2,
+                followers: None,
+            },
+            follower
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn test_check_metrics_endpoint_of_standalone_server() -> Result<()> {
+    let server = MockZkServer::start()?;
+
+    let metrics = check_metrics_endpoint(&server.address(), &ZookeeperVersion::v3_5_8)?;
+    assert_eq!(Some(ServerState::Standalone), metrics.server_state);
+    assert_eq!(5, metrics.znode_count);
+    Ok(())
+}
+
+#[test]
+fn test_check_metrics_endpoint_rejects_garbage() -> Result<()> {
+    let server = MockZkServer::start()?;
+    let version = ZookeeperVersion::v3_5_8;
+
+    // the port accepts connections but does not serve metrics
+    server.update(|state| state.metrics = Some("imok".to_string()));
+    assert!(check_metrics_endpoint(&server.address(), &version).is_err());
+
+    // valid format but without ZooKeeper metrics
+    server.update(|state| state.metrics = Some("jvm_threads_current 42.0\n".to_string()));
+    assert!(check_metrics_endpoint(&server.address(), &version).is_err());
+
+    // counts must be non-negative integers
+    server.update(|state| {
+        state.metrics = Some(
+            "zookeeper_NumAliveConnections -1.0\nzookeeper_OutstandingRequests 0.0\nzookeeper_NodeCount 5.0\n"
+                .to_string(),
+        )
+    });
+    assert!(check_metrics_endpoint(&server.address(), &version).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_native_prometheus_metrics() -> Result<()> {
+    let metrics = Metrics::parse(indoc!(
+        "
+        # TYPE znode_count gauge
+        znode_count 7.0
+        # TYPE num_alive_connections gauge
+        num_alive_connections 2.0
+        # TYPE outstanding_requests gauge
+        outstanding_requests 0.0
+        # TYPE learners gauge
+        learners 2.0
+        "
+    ))?;
+
+    let zk_metrics = ZkMetrics::from_metrics(&metrics, MetricsFormat::Prometheus)?;
+    assert_eq!(7, zk_metrics.znode_count);
+    assert_eq!(Some(2), zk_metrics.followers);
+    assert_eq!(None, zk_metrics.server_state);
+
+    // the JMX exporter names are not present
+    assert!(ZkMetrics::from_metrics(&metrics, MetricsFormat::JmxExporter).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_metrics_match_mntr() -> Result<()> {
+    let servers = MockZkServer::start_ensemble(&[
+        ServerState::Leader,
+        ServerState::Follower,
+        ServerState::Follower,
+    ])?;
+    // the JMX exporter format reports no follower count, so it is not compared
+    servers[0].update(|state| {
+        state.followers = 2;
+        state.znode_count = 120;
+        state.outstanding_requests = 3;
+    });
+
+    for version in &[ZookeeperVersion::v3_4_14, ZookeeperVersion::v3_5_8] {
+        for server in &servers {
+            compare_metrics_with_mntr(
+                &server.access(version)?,
+                server.host_name(),
+                &server.address(),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_metrics_diverging_from_mntr() -> Result<()> {
+    let server = MockZkServer::start()?;
+    let version = ZookeeperVersion::v3_5_8;
+
+    // e.g. the exporter scrapes the MBeans of another server
+    server.update(|state| {
+        state.metrics = Some(
+            "zookeeper_NumAliveConnections{replicaId=\"2\",memberType=\"Follower\",} 1.0\nzookeeper_OutstandingRequests{replicaId=\"2\",memberType=\"Follower\",} 0.0\nzookeeper_InMemoryDataTree_NodeCount{replicaId=\"2\",memberType=\"Follower\",} 500.0\n"
+                .to_string(),
+        )
+    });
+
+    let err = compare_metrics_with_mntr(
+        &server.access(&version)?,
+        server.host_name(),
+        &server.address(),
+    )
+    .unwrap_err()
+    .to_string();
+    assert!(err.contains("znode count"), "{}", err);
+    assert!(err.contains("server state"), "{}", err);
+    assert!(!err.contains("outstanding requests"), "{}", err);
+    Ok(())
+}
+
+#[test]
+fn test_verify_metrics_match_mntr_tolerance() -> Result<()> {
+    let server = MockZkServer::start()?;
+    server.update(|state| {
+        state.mode = ServerState::Leader;
+        state.followers = 2;
+        state.znode_count = 10;
+    });
+    let mntr = send_mntr(
+        &server.access(&ZookeeperVersion::v3_5_8)?,
+        server.host_name(),
+    )?;
+
+    let metrics = ZkMetrics {
+        server_state: None,
+        znode_count: 12,
+        alive_connections: 1,
+        outstanding_requests: 0,
+        followers: Some(2),
+    };
+    verify_metrics_match_mntr(&mntr, &metrics, 2)?;
+    assert!(verify_metrics_match_mntr(&mntr, &metrics, 1).is_err());
+
+    let metrics = ZkMetrics {
+        followers: Some(4),
+        ..metrics
+    };
+    assert!(verify_metrics_match_mntr(&mntr, &metrics, 2).is_err());
+    Ok(())
+}
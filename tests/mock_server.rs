@@ -0,0 +1,233 @@
+pub mod common;
+
+use crate::common::checks::{
+    check_ensemble_roles, check_ensemble_size, get_server_states, verify_ensemble_roles,
+    verify_ensemble_size, wait_for_leader,
+};
+use crate::common::four_letter_commands::{
+    get_server_id, send_4lw, send_4lw_i_am_ok, send_mntr, ServerState, ARE_YOU_OK, I_AM_OK,
+};
+use crate::common::mock_server::MockZkServer;
+use anyhow::Result;
+use integration_test_commons::test::prelude::Pod;
+use stackable_zookeeper_crd::ZookeeperVersion;
+use std::time::Duration;
+
+#[test]
+fn test_send_4lw() -> Result<()> {
+    let server = MockZkServer::start()?;
+
+    assert_eq!(
+        I_AM_OK,
+        send_4lw(
+            &server.access(&ZookeeperVersion::v3_4_14)?,
+            ARE_YOU_OK,
+            server.host_name()
+        )?
+    );
+    assert_eq!(
+        ARE_YOU_OK,
+        send_4lw(
+            &server.access(&ZookeeperVersion::v3_5_8)?,
+            ARE_YOU_OK,
+            server.host_name()
+        )?
+    );
+    Ok(())
+}
+
+#[test]
+fn test_send_4lw_i_am_ok_after_delayed_readiness() -> Result<()> {
+    for version in &[ZookeeperVersion::v3_4_14, ZookeeperVersion::v3_5_8] {
+        let server = MockZkServer::start()?;
+        server.ready_after(Duration::from_secs(3));
+
+        assert!(send_mntr(&server.access(version)?, server.host_name()).is_err());
+        send_4lw_i_am_ok(&server.pod(), &server.access(version)?)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_send_mntr_requires_whitelisted_command() -> Result<()> {
+    let server = MockZkServer::start()?;
+    server.whitelist(&[ARE_YOU_OK]);
+
+    assert!(send_mntr(
+        &server.access(&ZookeeperVersion::v3_4_14)?,
+        server.host_name()
+    )
+    .is_err());
+    assert_eq!(
+        I_AM_OK,
+        send_4lw(
+            &server.access(&ZookeeperVersion::v3_4_14)?,
+            ARE_YOU_OK,
+            server.host_name()
+        )?
+    );
+    // the whitelist does not apply to the admin server
+    send_mntr(
+        &server.access(&ZookeeperVersion::v3_5_8)?,
+        server.host_name(),
+    )?;
+    Ok(())
+}
+
+#[test]
+fn test_admin_server_error_response() -> Result<()> {
+    let server = MockZkServer::start()?;
+    server.fail_command("monitor", "Something went wrong");
+
+    assert!(send_mntr(
+        &server.access(&ZookeeperVersion::v3_5_8)?,
+        server.host_name()
+    )
+    .is_err());
+    assert_eq!(
+        ARE_YOU_OK,
+        send_4lw(
+            &server.access(&ZookeeperVersion::v3_5_8)?,
+            ARE_YOU_OK,
+            server.host_name()
+        )?
+    );
+    Ok(())
+}
+
+#[test]
+fn test_get_server_id() -> Result<()> {
+    let server = MockZkServer::start()?;
+    server.update(|state| state.server_id = 3);
+
+    for version in &[ZookeeperVersion::v3_4_14, ZookeeperVersion::v3_5_8] {
+        assert_eq!(
+            3,
+            get_server_id(&server.access(version)?, server.host_name())?
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn test_check_ensemble_roles() -> Result<()> {
+    let servers = MockZkServer::start_ensemble(&[
+        ServerState::Leader,
+        ServerState::Follower,
+        ServerState::Follower,
+    ])?;
+    let pods = pods(&servers);
+
+    for version in &[ZookeeperVersion::v3_4_14, ZookeeperVersion::v3_5_8] {
+        check_ensemble_roles(&pods, &servers[0].access(version)?)?;
+
+        let mntr = send_mntr(&servers[0].access(version)?, servers[0].host_name())?;
+        assert_eq!(Some(2), mntr.followers);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_check_ensemble_roles_standalone() -> Result<()> {
+    let server = MockZkServer::start()?;
+
+    check_ensemble_roles(&[server.pod()], &server.access(&ZookeeperVersion::v3_5_8)?)
+}
+
+#[test]
+fn test_invalid_ensemble_roles() -> Result<()> {
+    let servers = MockZkServer::start_ensemble(&[
+        ServerState::Leader,
+        ServerState::Leader,
+        ServerState::Follower,
+    ])?;
+
+    let states = get_server_states(
+        &pods(&servers),
+        &servers[0].access(&ZookeeperVersion::v3_5_8)?,
+    )?;
+    assert!(verify_ensemble_roles(&states).is_err());
+
+    // the ensemble recovers after a new election
+    servers[1].update(|state| state.mode = ServerState::Follower);
+    let states = get_server_states(
+        &pods(&servers),
+        &servers[0].access(&ZookeeperVersion::v3_5_8)?,
+    )?;
+    verify_ensemble_roles(&states)
+}
+
+#[test]
+fn test_check_ensemble_size() -> Result<()> {
+    let servers = MockZkServer::start_ensemble(&[
+        ServerState::Follower,
+        ServerState::Leader,
+        ServerState::Follower,
+        ServerState::Follower,
+        ServerState::Follower,
+    ])?;
+    let version = ZookeeperVersion::v3_5_8;
+
+    check_ensemble_size(&pods(&servers), &servers[0].access(&version)?)?;
+
+    // a server which joined the ensemble but is not in sync yet
+    servers[1].update(|state| state.synced_followers = 3);
+    let mntrs = servers
+        .iter()
+        .map(|server| {
+            Ok((
+                server.address(),
+                send_mntr(&server.access(&version)?, server.host_name())?,
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    assert!(verify_ensemble_size(&mntrs).is_err());
+
+    // the leader does not know about the fifth server
+    servers[1].update(|state| {
+        state.followers = 3;
+        state.synced_followers = 3;
+    });
+    let mntrs = servers
+        .iter()
+        .map(|server| {
+            Ok((
+                server.address(),
+                send_mntr(&server.access(&version)?, server.host_name())?,
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    assert!(verify_ensemble_size(&mntrs).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_wait_for_leader() -> Result<()> {
+    // the remaining servers after the leader was killed, still electing a new leader
+    let servers = MockZkServer::start_ensemble(&[ServerState::Follower, ServerState::Leader])?;
+    for server in &servers {
+        server.ready_after(Duration::from_secs(1));
+    }
+    let version = ZookeeperVersion::v3_4_14;
+
+    let leader = wait_for_leader(
+        &pods(&servers),
+        &servers[0].access(&version)?,
+        Duration::from_secs(10),
+    )?;
+    assert_eq!(servers[1].pod().metadata.name, Some(leader));
+
+    // no leader was elected
+    servers[1].update(|state| state.mode = ServerState::Follower);
+    assert!(wait_for_leader(
+        &pods(&servers),
+        &servers[0].access(&version)?,
+        Duration::from_secs(2)
+    )
+    .is_err());
+    Ok(())
+}
+
+fn pods(servers: &[MockZkServer]) -> Vec<Pod> {
+    servers.iter().map(MockZkServer::pod).collect()
+}
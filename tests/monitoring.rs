@@ -1,52 +1,69 @@
 pub mod common;
 
-use crate::common::checks::{custom_checks, custom_monitoring_checks};
+use crate::common::capabilities::ZkCapabilities;
+use crate::common::checks::{check_metrics_match_mntr, custom_checks, custom_monitoring_checks};
+use crate::common::diagnostics::with_diagnostics;
+use crate::common::four_letter_commands::{ServerAccess, DEFAULT_CLIENT_PORT};
 use crate::common::zookeeper::append_random_characters;
 
 use anyhow::Result;
-use common::zookeeper::{build_test_cluster, build_zk_cluster_with_metrics_and_client_port};
+use common::zookeeper::{build_test_cluster, ZkClusterBuilder};
 use stackable_zookeeper_crd::ZookeeperVersion;
 
-#[test]
-fn test_monitoring_and_container_ports() -> Result<()> {
+/// Number of servers of the ensemble, so the metrics of a leader and of followers are compared
+/// with their "mntr" statistics.
+const REPLICAS: usize = 3;
+
+version_tests!(test_monitoring_and_container_ports);
+
+fn test_monitoring_and_container_ports(version: &ZookeeperVersion) -> Result<()> {
     let name = append_random_characters("simple");
     let container_name = "zookeeper";
-    let client_port = 2181;
+    let client_port = DEFAULT_CLIENT_PORT;
     let metrics_port = 9505;
-    let version = ZookeeperVersion::v3_5_8;
+    let capabilities = ZkCapabilities::for_version(version)?;
+    let access = ServerAccess::new(version)?.client_port(client_port);
 
     let mut cluster = build_test_cluster();
 
-    let (zookeeper_cr, expected_pod_count) = build_zk_cluster_with_metrics_and_client_port(
+    with_diagnostics(
+        &format!("test_monitoring_and_container_ports_{}", version),
+        &mut cluster,
         &name,
-        &version,
-        1,
-        client_port,
-        metrics_port,
-    )?;
-    cluster.create_or_update(&zookeeper_cr, expected_pod_count)?;
-    let created_pods = cluster.list_pods();
-
-    custom_checks(
-        &cluster.client,
-        created_pods.as_slice(),
-        &version,
-        8080,
-        expected_pod_count,
-    )?;
-
-    // container names must to be lowercase
-    let container_ports = vec![
-        ("metrics", metrics_port),
-        ("client", client_port),
-        ("admin", 8080),
-    ];
-
-    custom_monitoring_checks(
-        created_pods.as_slice(),
-        container_ports.as_slice(),
-        container_name,
-    )?;
-
-    Ok(())
+        |cluster| {
+            let (zookeeper_cr, expected_pod_count) = ZkClusterBuilder::new(&name, version)
+                .replicas(REPLICAS)
+                .client_port(client_port)
+                .metrics_port(metrics_port)
+                .build()?;
+            cluster.create_or_update(&zookeeper_cr, expected_pod_count)?;
+            let created_pods = cluster.list_pods();
+
+            custom_checks(
+                &cluster.client,
+                created_pods.as_slice(),
+                &access,
+                expected_pod_count,
+            )?;
+
+            // container names must to be lowercase
+            let mut container_ports = vec![("metrics", metrics_port), ("client", client_port)];
+            // only versions with an admin server expose the admin port
+            if let Some(admin_port) = capabilities.admin_port {
+                container_ports.push(("admin", admin_port));
+            }
+
+            custom_monitoring_checks(
+                created_pods.as_slice(),
+                version,
+                container_ports.as_slice(),
+                container_name,
+            )?;
+
+            // the server state reported by the metrics must match the role of every server
+            check_metrics_match_mntr(created_pods.as_slice(), container_name, &access)?;
+
+            Ok(())
+        },
+    )
 }
@@ -0,0 +1,149 @@
+pub mod common;
+
+use crate::common::prometheus::{MetricType, Metrics};
+use anyhow::Result;
+use indoc::indoc;
+
+#[test]
+fn test_parse_jmx_exporter_metrics() -> Result<()> {
+    let metrics = Metrics::parse(indoc!(
+        r#"
+        # HELP zookeeper_NumAliveConnections NumAliveConnections (org.apache.ZooKeeperService<name0=ReplicatedServer_id1, name1=replica.1, name2=Leader><>NumAliveConnections)
+        # TYPE zookeeper_NumAliveConnections untyped
+        zookeeper_NumAliveConnections{replicaId="1",memberType="Leader",} 3.0
+        # HELP jvm_threads_current Current thread count of a JVM
+        # TYPE jvm_threads_current gauge
+        jvm_threads_current 42.0
+        # TYPE jmx_config_reload_success_total counter
+        jmx_config_reload_success_total 0.0 1625000000000
+        "#
+    ))?;
+
+    let connections = metrics.family("zookeeper_NumAliveConnections").unwrap();
+    assert_eq!(MetricType::Untyped, connections.metric_type);
+    assert!(connections.help.as_ref().unwrap().contains("name2=Leader"));
+    assert_eq!("Leader", connections.samples[0].labels["memberType"]);
+    assert_eq!("1", connections.samples[0].labels["replicaId"]);
+    assert_eq!(Some(3.0), metrics.value("zookeeper_NumAliveConnections"));
+
+    assert_eq!(
+        MetricType::Gauge,
+        metrics.family("jvm_threads_current").unwrap().metric_type
+    );
+    let reloads = metrics.samples("jmx_config_reload_success_total");
+    assert_eq!(Some(1625000000000), reloads[0].timestamp);
+    Ok(())
+}
+
+#[test]
+fn test_parse_label_escapes_and_special_values() -> Result<()> {
+    let metrics = Metrics::parse(indoc!(
+        r#"
+        # HELP escaped Line one\nline two with \\ backslash
+        escaped{path="C:\\data",quote="say \"hi\"",multi="a\nb"} NaN
+        escaped{path="/",quote="",multi=""} +Inf
+        negative -Inf
+        exponent 1.5e3
+        "#
+    ))?;
+
+    let samples = metrics.samples("escaped");
+    assert_eq!("C:\\data", samples[0].labels["path"]);
+    assert_eq!("say \"hi\"", samples[0].labels["quote"]);
+    assert_eq!("a\nb", samples[0].labels["multi"]);
+    assert!(samples[0].value.is_nan());
+    assert_eq!(f64::INFINITY, samples[1].value);
+    assert_eq!(
+        "Line one\nline two with \\ backslash",
+        metrics.family("escaped").unwrap().help.as_ref().unwrap()
+    );
+    assert_eq!(Some(f64::NEG_INFINITY), metrics.value("negative"));
+    assert_eq!(Some(1500.0), metrics.value("exponent"));
+    Ok(())
+}
+
+#[test]
+fn test_parse_histogram() -> Result<()> {
+    let metrics = Metrics::parse(indoc!(
+        r#"
+        # HELP request_latency Request latency in seconds.
+        # TYPE request_latency histogram
+        request_latency_bucket{type="read",le="0.1"} 5
+        request_latency_bucket{type="read",le="1"} 8
+        request_latency_bucket{type="read",le="+Inf"} 9
+        request_latency_sum{type="read"} 3.2
+        request_latency_count{type="read"} 9
+        request_latency_bucket{type="write",le="+Inf"} 0
+        request_latency_bucket{type="write",le="0.1"} 0
+        request_latency_sum{type="write"} 0
+        request_latency_count{type="write"} 0
+        # TYPE gc_seconds summary
+        gc_seconds{quantile="0.5"} 0.01
+        gc_seconds_sum 0.5
+        gc_seconds_count 12
+        "#
+    ))?;
+
+    let histograms = metrics.histograms("request_latency")?;
+    assert_eq!(2, histograms.len());
+    let read = &histograms[0];
+    assert_eq!("read", read.labels["type"]);
+    assert_eq!(
+        vec![(0.1, 5.0), (1.0, 8.0), (f64::INFINITY, 9.0)],
+        read.buckets
+    );
+    assert_eq!(3.2, read.sum);
+    assert_eq!(9.0, read.count);
+    // buckets are ordered by their upper bound
+    assert_eq!(f64::INFINITY, histograms[1].buckets[1].0);
+
+    let summary = metrics.family("gc_seconds").unwrap();
+    assert_eq!(MetricType::Summary, summary.metric_type);
+    assert_eq!(3, summary.samples.len());
+    assert!(metrics.histograms("gc_seconds").is_err());
+    Ok(())
+}
+
+#[test]
+fn test_invalid_histograms() -> Result<()> {
+    for histogram in &[
+        // not cumulative
+        "h_bucket{le=\"1\"} 5\nh_bucket{le=\"+Inf\"} 4\nh_sum 1\nh_count 4\n",
+        // missing +Inf bucket
+        "h_bucket{le=\"1\"} 5\nh_sum 1\nh_count 5\n",
+        // count does not match the +Inf bucket
+        "h_bucket{le=\"+Inf\"} 5\nh_sum 1\nh_count 6\n",
+    ] {
+        let metrics = Metrics::parse(&format!("# TYPE h histogram\n{}", histogram))?;
+        assert!(
+            metrics.histograms("h").is_err(),
+            "accepted histogram {}",
+            histogram
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn test_reject_garbage() {
+    for garbage in &[
+        "<html><body>Not Found</body></html>",
+        "zk_server_state\tleader",
+        "metric{label=\"unterminated} 1",
+        "metric{label=unquoted} 1",
+        "metric{label=\"a\" other=\"b\"} 1",
+        "metric{label=\"a\",label=\"b\"} 1",
+        "metric",
+        "metric one",
+        "metric 1 2 3",
+        "1metric 1",
+        "# TYPE metric unknown",
+        "metric 1\n# TYPE metric gauge",
+    ] {
+        assert!(
+            Metrics::parse(garbage).is_err(),
+            "accepted garbage [{}]",
+            garbage
+        );
+    }
+}
@@ -0,0 +1,158 @@
+pub mod common;
+
+use crate::common::retry::{
+    is_network_error, RetryPolicy, ENV_INITIAL_DELAY_MILLIS, ENV_JITTER, ENV_TIMEOUT_FACTOR,
+};
+use anyhow::{anyhow, Result};
+use std::env;
+use std::io;
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// The policies read environment variables, so tests modifying them must not run in parallel.
+static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+fn lock_env() -> MutexGuard<'static, ()> {
+    ENV_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn fast_policy(max_duration: Duration) -> RetryPolicy {
+    RetryPolicy::new(max_duration)
+        .initial_delay(Duration::from_millis(10))
+        .max_delay(Duration::from_millis(40))
+}
+
+#[test]
+fn test_retry_until_success() -> Result<()> {
+    let _env = lock_env();
+    let mut calls = 0;
+
+    let result = fast_policy(Duration::from_secs(5)).retry("test", || {
+        calls += 1;
+        if calls < 3 {
+            Err(anyhow!("not yet"))
+        } else {
+            Ok(calls)
+        }
+    })?;
+
+    assert_eq!(3, result);
+    Ok(())
+}
+
+#[test]
+fn test_retry_timeout_reports_every_attempt() {
+    let _env = lock_env();
+    let mut calls = 0;
+
+    let err = fast_policy(Duration::from_millis(200))
+        .retry("test", || -> Result<()> {
+            calls += 1;
+            Err(anyhow!("failure {}", calls))
+        })
+        .unwrap_err()
+        .to_string();
+
+    assert!(calls > 2);
+    assert!(err.starts_with("Timeout for [test]"));
+    for call in 1..=calls {
+        assert!(err.contains(&format!("attempt [{}]", call)));
+        assert!(err.contains(&format!("failure {}", call)));
+    }
+}
+
+#[test]
+fn test_retry_stops_on_non_retryable_error() {
+    let _env = lock_env();
+    let mut calls = 0;
+
+    let result = fast_policy(Duration::from_secs(5))
+        .retry_if(is_network_error)
+        .retry("test", || -> Result<()> {
+            calls += 1;
+            Err(anyhow!("malformed response"))
+        });
+
+    assert!(result.is_err());
+    assert_eq!(1, calls);
+}
+
+#[test]
+fn test_is_network_error() {
+    let refused = anyhow::Error::new(io::Error::new(io::ErrorKind::ConnectionRefused, "refused"));
+
+    assert!(is_network_error(&refused));
+    assert!(is_network_error(&refused.context("connect to node-1:2181")));
+    assert!(!is_network_error(&anyhow!("malformed response")));
+}
+
+#[test]
+fn test_backoff_respects_max_duration() -> Result<()> {
+    let _env = lock_env();
+    let start = Instant::now();
+
+    let result = RetryPolicy::new(Duration::from_millis(300))
+        .initial_delay(Duration::from_secs(10))
+        .retry("test", || -> Result<()> { Err(anyhow!("failure")) });
+
+    assert!(result.is_err());
+    assert!(start.elapsed() < Duration::from_secs(2));
+    Ok(())
+}
+
+#[test]
+fn test_environment_overrides() {
+    let _env = lock_env();
+
+    // stretch the max duration: 100ms * 3 with attempts every 10ms
+    env::set_var(ENV_TIMEOUT_FACTOR, "3");
+    env::set_var(ENV_INITIAL_DELAY_MILLIS, "10");
+    let start = Instant::now();
+    let mut calls = 0;
+    let result = RetryPolicy::new(Duration::from_millis(100))
+        .backoff_factor(1.0)
+        .retry("test", || {
+            calls += 1;
+            if start.elapsed() > Duration::from_millis(150) {
+                Ok(())
+            } else {
+                Err(anyhow!("not yet"))
+            }
+        });
+    env::remove_var(ENV_TIMEOUT_FACTOR);
+    env::remove_var(ENV_INITIAL_DELAY_MILLIS);
+
+    assert!(result.is_ok());
+    assert!(calls > 2);
+
+    env::set_var(ENV_JITTER, "2.0");
+    let result = RetryPolicy::new(Duration::from_secs(1)).retry("test", || Ok(()));
+    env::remove_var(ENV_JITTER);
+
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_values_set_in_code_win_over_environment() {
+    let _env = lock_env();
+
+    // the initial delay of one second set in code is kept, so the first attempt is only
+    // followed by the last one when the max duration is reached
+    env::set_var(ENV_INITIAL_DELAY_MILLIS, "10");
+    env::set_var(ENV_JITTER, "2.0");
+    let mut calls = 0;
+    let result: Result<()> = RetryPolicy::new(Duration::from_millis(100))
+        .initial_delay(Duration::from_secs(1))
+        .jitter(0.0)
+        .retry("test", || {
+            calls += 1;
+            Err(anyhow!("not yet"))
+        });
+    env::remove_var(ENV_INITIAL_DELAY_MILLIS);
+    env::remove_var(ENV_JITTER);
+
+    assert!(result.is_err());
+    assert_eq!(2, calls);
+}
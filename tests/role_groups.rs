@@ -0,0 +1,46 @@
+pub mod common;
+
+use crate::common::checks::{check_role_group_placement, check_znode_round_trip, custom_checks};
+use crate::common::diagnostics::with_diagnostics;
+use crate::common::four_letter_commands::{ServerAccess, DEFAULT_CLIENT_PORT};
+use crate::common::zookeeper::{
+    append_random_characters, build_test_cluster, zk_cluster_with_node_role_groups,
+};
+use anyhow::Result;
+use stackable_zookeeper_crd::ZookeeperVersion;
+
+#[test]
+fn test_create_cluster_with_role_groups() -> Result<()> {
+    let name = append_random_characters("groups");
+    let version = ZookeeperVersion::v3_5_8;
+    let mut cluster = build_test_cluster();
+
+    with_diagnostics(
+        "test_create_cluster_with_role_groups",
+        &mut cluster,
+        &name,
+        |cluster| {
+            let builder =
+                zk_cluster_with_node_role_groups(&name, &version, &[("1", 1), ("2", 1), ("3", 1)]);
+            let (zookeeper_cr, expected_pod_count) = builder.build()?;
+            cluster.create_or_update(&zookeeper_cr, expected_pod_count)?;
+            let created_pods = cluster.list_pods();
+
+            check_role_group_placement(
+                &cluster.client,
+                created_pods.as_slice(),
+                builder.role_groups(),
+            )?;
+
+            custom_checks(
+                &cluster.client,
+                created_pods.as_slice(),
+                &ServerAccess::new(&version)?,
+                expected_pod_count,
+            )?;
+            check_znode_round_trip(created_pods.as_slice(), DEFAULT_CLIENT_PORT)?;
+
+            Ok(())
+        },
+    )
+}
@@ -1,79 +1,61 @@
 pub mod common;
 
-use crate::common::checks::custom_checks;
+use crate::common::checks::{check_ensemble_size, check_znodes, custom_checks, write_znodes};
+use crate::common::diagnostics::with_diagnostics;
+use crate::common::four_letter_commands::{ServerAccess, DEFAULT_CLIENT_PORT};
+use crate::common::known_issues::{expect_failure, CONFIG_MAPS_NOT_UPDATED_ON_SCALING};
 use crate::common::zookeeper::{append_random_characters, build_test_cluster, build_zk_cluster};
 
 use anyhow::Result;
 use stackable_zookeeper_crd::ZookeeperVersion;
 
-// This will cause the integration tests to fail because config maps are not updated correctly. This
-// can be activated once https://github.com/stackabletech/zookeeper-operator/issues/128 is fixed.
-#[test]
-#[ignore]
-fn test_scale_cluster_up() -> Result<()> {
-    let name = append_random_characters("simple");
-    let version = ZookeeperVersion::v3_4_14;
-    let (zookeeper_cr, expected_pod_count) = build_zk_cluster(&name, &version, 1)?;
-
-    let mut cluster = build_test_cluster();
-    cluster.create_or_update(&zookeeper_cr, expected_pod_count)?;
-    let created_pods = cluster.list_pods();
-
-    custom_checks(
-        &cluster.client,
-        created_pods.as_slice(),
-        &version,
-        2181,
-        expected_pod_count,
-    )?;
+/// The replicas the cluster is scaled through one after another. The largest ensemble requires
+/// as many nodes.
+const SCALE_STEPS: &[usize] = &[1, 3, 5, 3, 1];
 
-    let (zookeeper_cr, expected_pod_count) = build_zk_cluster(&name, &version, 3)?;
-    cluster.create_or_update(&zookeeper_cr, expected_pod_count)?;
-    let created_pods = cluster.list_pods();
+/// Number of znodes written at every step which must be readable in all later steps.
+const ZNODES_PER_STEP: usize = 10;
 
-    custom_checks(
-        &cluster.client,
-        created_pods.as_slice(),
-        &version,
-        2181,
-        expected_pod_count,
-    )?;
+version_tests!(test_scale_cluster);
 
-    Ok(())
+fn test_scale_cluster(version: &ZookeeperVersion) -> Result<()> {
+    expect_failure(&CONFIG_MAPS_NOT_UPDATED_ON_SCALING, || {
+        scale_cluster(&format!("test_scale_cluster_{}", version), version)
+    })
 }
 
-// This will cause the integration tests to fail because config maps are not updated correctly. This
-// can be activated once https://github.com/stackabletech/zookeeper-operator/issues/128 is fixed.
-#[test]
-#[ignore]
-fn test_scale_cluster_down() -> Result<()> {
+fn scale_cluster(test_name: &str, version: &ZookeeperVersion) -> Result<()> {
     let name = append_random_characters("simple");
-    let version = ZookeeperVersion::v3_4_14;
+    let access = ServerAccess::new(version)?;
     let mut cluster = build_test_cluster();
 
-    let (zookeeper_cr, expected_pod_count) = build_zk_cluster(&name, &version, 3)?;
-    cluster.create_or_update(&zookeeper_cr, expected_pod_count)?;
-    let created_pods = cluster.list_pods();
-
-    custom_checks(
-        &cluster.client,
-        created_pods.as_slice(),
-        &version,
-        2181,
-        expected_pod_count,
-    )?;
-
-    let (zookeeper_cr, expected_pod_count) = build_zk_cluster(&name, &version, 1)?;
-    cluster.create_or_update(&zookeeper_cr, expected_pod_count)?;
-    let created_pods = cluster.list_pods();
-
-    custom_checks(
-        &cluster.client,
-        created_pods.as_slice(),
-        &version,
-        2181,
-        expected_pod_count,
-    )?;
-
-    Ok(())
+    with_diagnostics(test_name, &mut cluster, &name, |cluster| {
+        let mut znodes = Vec::new();
+
+        for replicas in SCALE_STEPS {
+            println!("Scaling cluster [{}] to [{}] servers", name, replicas);
+
+            let (zookeeper_cr, expected_pod_count) = build_zk_cluster(&name, version, *replicas)?;
+            cluster.create_or_update(&zookeeper_cr, expected_pod_count)?;
+            let pods = cluster.list_pods();
+
+            // checks the server.N entries, the server ids and that there is exactly one leader
+            custom_checks(
+                &cluster.client,
+                pods.as_slice(),
+                &access,
+                expected_pod_count,
+            )?;
+            check_ensemble_size(pods.as_slice(), &access)?;
+
+            check_znodes(pods.as_slice(), DEFAULT_CLIENT_PORT, &znodes)?;
+            znodes.extend(write_znodes(
+                pods.as_slice(),
+                DEFAULT_CLIENT_PORT,
+                ZNODES_PER_STEP,
+            )?);
+        }
+
+        Ok(())
+    })
 }
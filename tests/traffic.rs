@@ -0,0 +1,42 @@
+pub mod common;
+
+use crate::common::traffic::TrafficReport;
+use std::time::Duration;
+
+fn report() -> TrafficReport {
+    TrafficReport {
+        root: "/integration-test-traffic".to_string(),
+        acknowledged: vec![("/integration-test-traffic/write-0".to_string(), vec![1])],
+        longest_outage: Duration::from_secs(5),
+        duration: Duration::from_secs(60),
+        ..TrafficReport::default()
+    }
+}
+
+#[test]
+fn test_verify_traffic_report() {
+    assert!(report().verify(Duration::from_secs(10)).is_ok());
+}
+
+#[test]
+fn test_verify_traffic_report_detects_lost_writes() {
+    let mut report = report();
+    report
+        .lost
+        .push("/integration-test-traffic/write-0".to_string());
+
+    assert!(report.verify(Duration::from_secs(10)).is_err());
+}
+
+#[test]
+fn test_verify_traffic_report_detects_exceeded_budget() {
+    assert!(report().verify(Duration::from_secs(1)).is_err());
+}
+
+#[test]
+fn test_verify_traffic_report_requires_acknowledged_writes() {
+    let mut report = report();
+    report.acknowledged.clear();
+
+    assert!(report.verify(Duration::from_secs(10)).is_err());
+}
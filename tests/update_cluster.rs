@@ -1,13 +1,25 @@
 pub mod common;
 
-use crate::common::checks::custom_checks;
+use crate::common::checks::{
+    check_pod_versions, check_znodes, custom_checks, node_address, write_znodes,
+};
+use crate::common::diagnostics::with_diagnostics;
+use crate::common::four_letter_commands::{ServerAccess, DEFAULT_CLIENT_PORT};
+use crate::common::retry::scaled_timeout;
+use crate::common::traffic::BackgroundTraffic;
 use crate::common::zookeeper::{append_random_characters, build_test_cluster, build_zk_cluster};
 use anyhow::Result;
-use integration_test_commons::test::prelude::Pod;
 use stackable_zookeeper_crd::ZookeeperVersion;
 use std::thread;
 use std::time::Duration;
 
+/// Number of znodes written before the upgrade which must be readable afterwards.
+const PRE_UPGRADE_ZNODES: usize = 20;
+
+/// Maximum time in seconds the ensemble may not accept writes during a rolling upgrade. Can be
+/// stretched via ZK_TEST_RETRY_TIMEOUT_FACTOR.
+const QUORUM_LOSS_BUDGET: u64 = 30;
+
 #[test]
 fn test_cluster_update() -> Result<()> {
     let name = append_random_characters("simple");
@@ -15,43 +27,100 @@ fn test_cluster_update() -> Result<()> {
     let version_update = ZookeeperVersion::v3_5_8;
     let mut cluster = build_test_cluster();
 
-    let (zookeeper_cr, expected_pod_count) = build_zk_cluster(&name, &version, 1)?;
-    cluster.create_or_update(&zookeeper_cr, expected_pod_count)?;
-    let created_pods = cluster.list_pods();
-
-    custom_checks(
-        &cluster.client,
-        created_pods.as_slice(),
-        &version,
-        2181,
-        expected_pod_count,
-    )?;
-    check_pod_version(&version, created_pods.as_slice());
-
-    let (zookeeper_cr, expected_pod_count) = build_zk_cluster(&name, &version_update, 1)?;
-    cluster.create_or_update(&zookeeper_cr, expected_pod_count)?;
-    let created_pods = cluster.list_pods();
-
-    custom_checks(
-        &cluster.client,
-        created_pods.as_slice(),
-        &version_update,
-        8080,
-        expected_pod_count,
-    )?;
-    check_pod_version(&version_update, created_pods.as_slice());
-
-    thread::sleep(Duration::from_secs(2));
-
-    Ok(())
+    with_diagnostics("test_cluster_update", &mut cluster, &name, |cluster| {
+        let (zookeeper_cr, expected_pod_count) = build_zk_cluster(&name, &version, 1)?;
+        cluster.create_or_update(&zookeeper_cr, expected_pod_count)?;
+        let created_pods = cluster.list_pods();
+
+        custom_checks(
+            &cluster.client,
+            created_pods.as_slice(),
+            &ServerAccess::new(&version)?,
+            expected_pod_count,
+        )?;
+        check_pod_versions(created_pods.as_slice(), &version)?;
+
+        let (zookeeper_cr, expected_pod_count) = build_zk_cluster(&name, &version_update, 1)?;
+        cluster.create_or_update(&zookeeper_cr, expected_pod_count)?;
+        let created_pods = cluster.list_pods();
+
+        custom_checks(
+            &cluster.client,
+            created_pods.as_slice(),
+            &ServerAccess::new(&version_update)?,
+            expected_pod_count,
+        )?;
+        check_pod_versions(created_pods.as_slice(), &version_update)?;
+
+        thread::sleep(Duration::from_secs(2));
+
+        Ok(())
+    })
 }
 
-fn check_pod_version(version: &ZookeeperVersion, pods: &[Pod]) {
-    for pod in pods {
-        let pod_version = &pod
-            .metadata
-            .labels
-            .get(stackable_operator::labels::APP_VERSION_LABEL);
-        assert_eq!(&Some(&version.to_string()), pod_version);
-    }
+#[test]
+fn test_rolling_upgrade_with_client_traffic() -> Result<()> {
+    let name = append_random_characters("upgrade");
+    let version = ZookeeperVersion::v3_4_14;
+    let version_update = ZookeeperVersion::v3_5_8;
+    let mut cluster = build_test_cluster();
+
+    with_diagnostics(
+        "test_rolling_upgrade_with_client_traffic",
+        &mut cluster,
+        &name,
+        |cluster| {
+            let (zookeeper_cr, expected_pod_count) = build_zk_cluster(&name, &version, 3)?;
+            cluster.create_or_update(&zookeeper_cr, expected_pod_count)?;
+            let created_pods = cluster.list_pods();
+
+            custom_checks(
+                &cluster.client,
+                created_pods.as_slice(),
+                &ServerAccess::new(&version)?,
+                expected_pod_count,
+            )?;
+
+            let pre_upgrade_znodes = write_znodes(
+                created_pods.as_slice(),
+                DEFAULT_CLIENT_PORT,
+                PRE_UPGRADE_ZNODES,
+            )?;
+            let hosts = created_pods
+                .iter()
+                .map(|pod| node_address(pod, DEFAULT_CLIENT_PORT))
+                .collect::<Result<Vec<_>>>()?;
+            let traffic = BackgroundTraffic::start(hosts)?;
+
+            let (zookeeper_cr, expected_pod_count) = build_zk_cluster(&name, &version_update, 3)?;
+            let upgrade = cluster.create_or_update(&zookeeper_cr, expected_pod_count);
+            // keep the traffic running until the upgraded servers serve requests again
+            let upgraded_pods = cluster.list_pods();
+            let upgrade = upgrade.and_then(|_| {
+                custom_checks(
+                    &cluster.client,
+                    upgraded_pods.as_slice(),
+                    &ServerAccess::new(&version_update)?,
+                    expected_pod_count,
+                )
+            });
+            let report = traffic.stop()?;
+            upgrade?;
+
+            check_pod_versions(upgraded_pods.as_slice(), &version_update)?;
+            check_znodes(
+                upgraded_pods.as_slice(),
+                DEFAULT_CLIENT_PORT,
+                &pre_upgrade_znodes,
+            )?;
+            check_znodes(
+                upgraded_pods.as_slice(),
+                DEFAULT_CLIENT_PORT,
+                &report.acknowledged,
+            )?;
+            report.verify(scaled_timeout(Duration::from_secs(QUORUM_LOSS_BUDGET))?)?;
+
+            Ok(())
+        },
+    )
 }
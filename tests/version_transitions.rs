@@ -0,0 +1,88 @@
+pub mod common;
+
+use crate::common::checks::{
+    check_current_version, check_pod_versions, check_znodes, custom_checks, write_znodes,
+};
+use crate::common::diagnostics::with_diagnostics;
+use crate::common::four_letter_commands::{ServerAccess, DEFAULT_CLIENT_PORT};
+use crate::common::zookeeper::{
+    append_random_characters, build_test_cluster, build_zk_cluster, version_transition,
+    VersionTransition,
+};
+use anyhow::Result;
+use stackable_zookeeper_crd::ZookeeperVersion;
+
+/// Number of servers of the ensemble.
+const REPLICAS: usize = 3;
+
+/// Number of znodes written before the version change which must be readable afterwards.
+const PRE_TRANSITION_ZNODES: usize = 20;
+
+version_transition_tests!(test_version_transition);
+
+/// Changes the version of a 3 server ensemble and checks that the servers end up running a
+/// single version with all data preserved. Upgrades must be carried out. Downgrades may also
+/// be refused, in which case every server must keep running the current version.
+fn test_version_transition(from: &ZookeeperVersion, to: &ZookeeperVersion) -> Result<()> {
+    let name = append_random_characters("transition");
+    let transition = version_transition(from, to)?;
+    let mut cluster = build_test_cluster();
+
+    with_diagnostics(
+        &format!("test_version_transition_{}_to_{}", from, to),
+        &mut cluster,
+        &name,
+        |cluster| {
+            let (zookeeper_cr, expected_pod_count) = build_zk_cluster(&name, from, REPLICAS)?;
+            cluster.create_or_update(&zookeeper_cr, expected_pod_count)?;
+            let created_pods = cluster.list_pods();
+
+            custom_checks(
+                &cluster.client,
+                created_pods.as_slice(),
+                &ServerAccess::new(from)?,
+                expected_pod_count,
+            )?;
+            let znodes = write_znodes(
+                created_pods.as_slice(),
+                DEFAULT_CLIENT_PORT,
+                PRE_TRANSITION_ZNODES,
+            )?;
+
+            let (zookeeper_cr, expected_pod_count) = build_zk_cluster(&name, to, REPLICAS)?;
+            let applied = cluster.create_or_update(&zookeeper_cr, expected_pod_count);
+
+            let running_version = match (transition, applied) {
+                (VersionTransition::Upgrade, applied) => {
+                    applied?;
+                    to
+                }
+                (VersionTransition::Downgrade, Ok(()))
+                    if check_pod_versions(cluster.list_pods().as_slice(), to).is_ok() =>
+                {
+                    to
+                }
+                (VersionTransition::Downgrade, applied) => {
+                    println!(
+                        "Downgrade from [{}] to [{}] was not carried out ({:?}), expecting all servers to keep running [{}]",
+                        from, to, applied, from
+                    );
+                    from
+                }
+            };
+
+            let pods = cluster.list_pods();
+            check_pod_versions(pods.as_slice(), running_version)?;
+            check_current_version(&cluster.client, &name, running_version)?;
+            custom_checks(
+                &cluster.client,
+                pods.as_slice(),
+                &ServerAccess::new(running_version)?,
+                expected_pod_count,
+            )?;
+            check_znodes(pods.as_slice(), DEFAULT_CLIENT_PORT, &znodes)?;
+
+            Ok(())
+        },
+    )
+}
@@ -0,0 +1,309 @@
+pub mod common;
+
+use crate::common::zk_client::{
+    is_zk_error, CreateMode, ZkClient, ERROR_BAD_VERSION, ERROR_NODE_EXISTS, ERROR_NOT_EMPTY,
+};
+use anyhow::Result;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+const SESSION_ID: i64 = 0x1234_5678;
+const SESSION_TIMEOUT_MILLIS: i32 = 6000;
+
+const OP_CREATE: i32 = 1;
+const OP_DELETE: i32 = 2;
+const OP_EXISTS: i32 = 3;
+const OP_GET_DATA: i32 = 4;
+const OP_SET_DATA: i32 = 5;
+const OP_GET_CHILDREN: i32 = 8;
+const OP_SYNC: i32 = 9;
+const OP_PING: i32 = 11;
+const OP_CLOSE_SESSION: i32 = -11;
+
+const ERROR_NO_NODE: i32 = -101;
+
+/// Accept a single client and run the script against its connection. Panics of the script
+/// (e.g. failed assertions) are raised when the returned handle is joined.
+fn serve<F>(script: F) -> (String, JoinHandle<()>)
+where
+    F: FnOnce(&mut TcpStream) + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    let handle = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        script(&mut stream);
+    });
+    (address, handle)
+}
+
+fn read_frame(stream: &mut TcpStream) -> Vec<u8> {
+    let mut length = [0u8; 4];
+    stream.read_exact(&mut length).unwrap();
+    let mut frame = vec![0u8; i32::from_be_bytes(length) as usize];
+    stream.read_exact(&mut frame).unwrap();
+    frame
+}
+
+fn write_frame(stream: &mut TcpStream, payload: &[u8]) {
+    stream
+        .write_all(&(payload.len() as i32).to_be_bytes())
+        .unwrap();
+    stream.write_all(payload).unwrap();
+}
+
+fn i32_at(frame: &[u8], position: usize) -> i32 {
+    let mut bytes = [0u8; 4];
+    bytes.copy_from_slice(&frame[position..position + 4]);
+    i32::from_be_bytes(bytes)
+}
+
+fn buffer(value: &[u8]) -> Vec<u8> {
+    let mut bytes = (value.len() as i32).to_be_bytes().to_vec();
+    bytes.extend_from_slice(value);
+    bytes
+}
+
+/// Jute serialized Stat with the given version and data length, all other fields are zero.
+fn stat(version: i32, data_length: i32) -> Vec<u8> {
+    let mut bytes = vec![0u8; 32];
+    bytes.extend_from_slice(&version.to_be_bytes());
+    bytes.extend_from_slice(&[0u8; 16]);
+    bytes.extend_from_slice(&data_length.to_be_bytes());
+    bytes.extend_from_slice(&[0u8; 12]);
+    bytes
+}
+
+/// Answer the ConnectRequest with the given negotiated timeout.
+fn handshake(stream: &mut TcpStream, timeout: i32) {
+    let request = read_frame(stream);
+    // protocol version, last zxid, timeout, session id, password, read only
+    assert_eq!(45, request.len());
+    assert_eq!(10_000, i32_at(&request, 12));
+
+    let mut response = 0i32.to_be_bytes().to_vec();
+    response.extend_from_slice(&timeout.to_be_bytes());
+    response.extend_from_slice(&SESSION_ID.to_be_bytes());
+    response.extend_from_slice(&buffer(&[0u8; 16]));
+    write_frame(stream, &response);
+}
+
+/// Read the next request, check its op code and return its xid.
+fn expect_request(stream: &mut TcpStream, op: i32) -> i32 {
+    let request = read_frame(stream);
+    assert_eq!(op, i32_at(&request, 4), "unexpected op code");
+    i32_at(&request, 0)
+}
+
+fn reply(stream: &mut TcpStream, xid: i32, err: i32, body: &[u8]) {
+    let mut frame = xid.to_be_bytes().to_vec();
+    frame.extend_from_slice(&1i64.to_be_bytes()); // zxid
+    frame.extend_from_slice(&err.to_be_bytes());
+    frame.extend_from_slice(body);
+    write_frame(stream, &frame);
+}
+
+#[test]
+fn test_connect_handshake() -> Result<()> {
+    let (address, server) = serve(|stream| {
+        handshake(stream, SESSION_TIMEOUT_MILLIS);
+        let xid = expect_request(stream, OP_CLOSE_SESSION);
+        reply(stream, xid, 0, &[]);
+    });
+
+    let client = ZkClient::connect(&address)?;
+    assert_eq!(SESSION_ID, client.session_id());
+    assert_eq!(
+        Duration::from_millis(SESSION_TIMEOUT_MILLIS as u64),
+        client.session_timeout()
+    );
+    client.close()?;
+
+    server.join().unwrap();
+    Ok(())
+}
+
+#[test]
+fn test_rejected_session() {
+    let (address, server) = serve(|stream| handshake(stream, 0));
+
+    assert!(ZkClient::connect(&address).is_err());
+    server.join().unwrap();
+}
+
+#[test]
+fn test_replies_of_every_operation() -> Result<()> {
+    let (address, server) = serve(|stream| {
+        handshake(stream, SESSION_TIMEOUT_MILLIS);
+
+        let xid = expect_request(stream, OP_CREATE);
+        reply(stream, xid, 0, &buffer(b"/test0000000001"));
+
+        let xid = expect_request(stream, OP_GET_DATA);
+        let mut body = buffer(b"data");
+        body.extend_from_slice(&stat(0, 4));
+        reply(stream, xid, 0, &body);
+
+        let xid = expect_request(stream, OP_SET_DATA);
+        reply(stream, xid, 0, &stat(1, 3));
+
+        let xid = expect_request(stream, OP_EXISTS);
+        reply(stream, xid, 0, &stat(1, 3));
+
+        let xid = expect_request(stream, OP_EXISTS);
+        reply(stream, xid, ERROR_NO_NODE, &[]);
+
+        let xid = expect_request(stream, OP_GET_CHILDREN);
+        let mut body = 2i32.to_be_bytes().to_vec();
+        body.extend_from_slice(&buffer(b"a"));
+        body.extend_from_slice(&buffer(b"b"));
+        reply(stream, xid, 0, &body);
+
+        let xid = expect_request(stream, OP_SYNC);
+        reply(stream, xid, 0, &buffer(b"/"));
+
+        let xid = expect_request(stream, OP_DELETE);
+        reply(stream, xid, 0, &[]);
+
+        // pings use the reserved xid -2
+        let xid = expect_request(stream, OP_PING);
+        assert_eq!(-2, xid);
+        reply(stream, xid, 0, &[]);
+
+        let xid = expect_request(stream, OP_CLOSE_SESSION);
+        reply(stream, xid, 0, &[]);
+    });
+
+    let mut client = ZkClient::connect(&address)?;
+    assert_eq!(
+        "/test0000000001",
+        client.create("/test", b"data", CreateMode::PersistentSequential)?
+    );
+
+    let (data, data_stat) = client.get_data("/test0000000001")?;
+    assert_eq!(b"data".to_vec(), data);
+    assert_eq!(4, data_stat.data_length);
+
+    assert_eq!(
+        1,
+        client.set_data("/test0000000001", b"new", Some(0))?.version
+    );
+    assert_eq!(
+        Some(3),
+        client
+            .exists("/test0000000001")?
+            .map(|stat| stat.data_length)
+    );
+    assert_eq!(None, client.exists("/missing")?);
+    assert_eq!(vec!["a", "b"], client.get_children("/")?);
+    client.sync("/")?;
+    client.delete("/test0000000001", None)?;
+    client.ping()?;
+    client.close()?;
+
+    server.join().unwrap();
+    Ok(())
+}
+
+#[test]
+fn test_error_codes() -> Result<()> {
+    let (address, server) = serve(|stream| {
+        handshake(stream, SESSION_TIMEOUT_MILLIS);
+        for err in &[ERROR_NODE_EXISTS, ERROR_BAD_VERSION, ERROR_NOT_EMPTY] {
+            let request = read_frame(stream);
+            reply(stream, i32_at(&request, 0), *err, &[]);
+        }
+        let xid = expect_request(stream, OP_PING);
+        reply(stream, xid, 0, &[]);
+    });
+
+    let mut client = ZkClient::connect(&address)?;
+    let err = client
+        .create("/test", &[], CreateMode::Persistent)
+        .unwrap_err();
+    assert!(is_zk_error(&err, ERROR_NODE_EXISTS), "{}", err);
+    let err = client.set_data("/test", &[], Some(7)).unwrap_err();
+    assert!(is_zk_error(&err, ERROR_BAD_VERSION), "{}", err);
+    let err = client.delete("/test", None).unwrap_err();
+    assert!(is_zk_error(&err, ERROR_NOT_EMPTY), "{}", err);
+
+    // error codes of the server leave the connection usable
+    assert!(!client.is_broken());
+    client.ping()?;
+
+    server.join().unwrap();
+    Ok(())
+}
+
+#[test]
+fn test_notification_before_reply() -> Result<()> {
+    let (address, server) = serve(|stream| {
+        handshake(stream, SESSION_TIMEOUT_MILLIS);
+        let xid = expect_request(stream, OP_GET_DATA);
+
+        // WatcherEvent: type NodeDataChanged, state SyncConnected, path
+        let mut event = 3i32.to_be_bytes().to_vec();
+        event.extend_from_slice(&3i32.to_be_bytes());
+        event.extend_from_slice(&buffer(b"/other"));
+        reply(stream, -1, 0, &event);
+
+        let mut body = buffer(b"data");
+        body.extend_from_slice(&stat(0, 4));
+        reply(stream, xid, 0, &body);
+    });
+
+    let mut client = ZkClient::connect(&address)?;
+    assert_eq!(b"data".to_vec(), client.get_data("/test")?.0);
+
+    server.join().unwrap();
+    Ok(())
+}
+
+#[test]
+fn test_truncated_frame_breaks_the_client() -> Result<()> {
+    let (address, server) = serve(|stream| {
+        handshake(stream, SESSION_TIMEOUT_MILLIS);
+        let xid = expect_request(stream, OP_GET_DATA);
+
+        // announce 100 bytes but send only the reply header before closing the connection
+        stream.write_all(&100i32.to_be_bytes()).unwrap();
+        stream.write_all(&xid.to_be_bytes()).unwrap();
+        stream.write_all(&1i64.to_be_bytes()).unwrap();
+    });
+
+    let mut client = ZkClient::connect(&address)?;
+    let err = client.get_data("/test").unwrap_err();
+    assert!(format!("{:#}", err).contains("[100] bytes"), "{:#}", err);
+    server.join().unwrap();
+
+    // the next request must not read the remainder of the truncated frame
+    assert!(client.is_broken());
+    let err = client.exists("/test").unwrap_err().to_string();
+    assert!(err.contains("requires a reconnect"), "{}", err);
+    assert!(!err.contains("xid"), "{}", err);
+    client.close()?;
+    Ok(())
+}
+
+#[test]
+fn test_unexpected_xid_breaks_the_client() -> Result<()> {
+    let (address, server) = serve(|stream| {
+        handshake(stream, SESSION_TIMEOUT_MILLIS);
+        let xid = expect_request(stream, OP_EXISTS);
+        reply(stream, xid + 1, 0, &stat(0, 0));
+    });
+
+    let mut client = ZkClient::connect(&address)?;
+    let err = client.exists("/test").unwrap_err().to_string();
+    assert!(err.contains("xid"), "{}", err);
+    assert!(client.is_broken());
+    assert!(client.ping().is_err());
+
+    server.join().unwrap();
+    Ok(())
+}
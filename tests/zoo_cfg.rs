@@ -0,0 +1,107 @@
+pub mod common;
+
+use crate::common::zoo_cfg::{ServerEntry, ServerRole, ZooCfg};
+use anyhow::Result;
+use indoc::indoc;
+
+#[test]
+fn test_parse_zoo_cfg() -> Result<()> {
+    let zoo_cfg = ZooCfg::parse(indoc!(
+        r#"
+        # generated by the operator
+        ! another comment style
+        tickTime=2000
+        dataDir : /tmp/zoo\
+                  keeper
+        clientPort 2181
+        4lw.commands.whitelist=srvr, \
+            mntr,ruok
+        initLimit=5
+        initLimit=10
+        escaped\=key=tab\tandA
+        server.1=node-1.stackable.test:2888:3888
+        server.2=node-2.stackable.test:2888:3888:participant;2181
+        server.3=[::1]:2888:3888:observer;0.0.0.0:2182
+        "#
+    ))?;
+
+    assert_eq!(Some(2000), zoo_cfg.tick_time()?);
+    assert_eq!(Some("/tmp/zookeeper"), zoo_cfg.data_dir());
+    assert_eq!(Some(2181), zoo_cfg.client_port()?);
+    assert_eq!(
+        vec!["srvr", "mntr", "ruok"],
+        zoo_cfg.four_letter_word_whitelist()
+    );
+    assert_eq!(Some("10"), zoo_cfg.get("initLimit"));
+    assert_eq!(Some("tab\tandA"), zoo_cfg.get("escaped=key"));
+
+    let servers = zoo_cfg.servers()?;
+    assert_eq!(
+        vec![
+            ServerEntry {
+                id: 1,
+                host: "node-1.stackable.test".to_string(),
+                peer_port: 2888,
+                election_port: 3888,
+                role: None,
+                client_address: None,
+                client_port: None,
+            },
+            ServerEntry {
+                id: 2,
+                host: "node-2.stackable.test".to_string(),
+                peer_port: 2888,
+                election_port: 3888,
+                role: Some(ServerRole::Participant),
+                client_address: None,
+                client_port: Some(2181),
+            },
+            ServerEntry {
+                id: 3,
+                host: "::1".to_string(),
+                peer_port: 2888,
+                election_port: 3888,
+                role: Some(ServerRole::Observer),
+                client_address: Some("0.0.0.0".to_string()),
+                client_port: Some(2182),
+            },
+        ],
+        servers
+    );
+    Ok(())
+}
+
+#[test]
+fn test_server_properties_do_not_match_other_keys() -> Result<()> {
+    // the former substring count considered these as servers as well
+    let zoo_cfg = ZooCfg::parse(indoc!(
+        "
+        # server.4=commented:2888:3888
+        quorum.auth.server.loginContext=QuorumServer
+        server.1=node-1:2888:3888
+        "
+    ))?;
+
+    assert_eq!(1, zoo_cfg.servers()?.len());
+    Ok(())
+}
+
+#[test]
+fn test_invalid_server_entries() -> Result<()> {
+    for invalid in &[
+        "server.1=node-1:2888:3888\nserver.01=node-2:2888:3888",
+        "server.1=node-1:2888",
+        "server.1=node-1:0:3888",
+        "server.1=node-1:2888:70000",
+        "server.1=node-1:2888:3888:voter",
+        "server.x=node-1:2888:3888",
+        "server.1=:2888:3888",
+    ] {
+        assert!(
+            ZooCfg::parse(invalid)?.servers().is_err(),
+            "expected [{}] to be invalid",
+            invalid
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,64 @@
+pub mod common;
+
+use crate::common::zookeeper::{
+    all_versions, version_transition, VersionTransition, ZkClusterBuilder, ZkRoleGroup,
+};
+use anyhow::Result;
+use stackable_zookeeper_crd::ZookeeperVersion;
+
+#[test]
+fn test_zk_cluster_builder() -> Result<()> {
+    let (zookeeper_cr, expected_pod_count) =
+        ZkClusterBuilder::new("simple", &ZookeeperVersion::v3_5_8)
+            .role_group("a", ZkRoleGroup::new(2).selector_label("node", "1"))
+            .role_group("b", ZkRoleGroup::new(1))
+            .client_port(2182)
+            .metrics_port(9505)
+            .config_override("zoo.cfg", "tickTime", "3000")
+            .build()?;
+
+    assert_eq!(3, expected_pod_count);
+
+    let spec = serde_json::to_value(&zookeeper_cr)?["spec"].clone();
+    let role_groups = &spec["servers"]["roleGroups"];
+    assert_eq!("3.5.8", spec["version"]);
+    assert_eq!(2, role_groups["a"]["replicas"]);
+    assert_eq!("1", role_groups["a"]["selector"]["matchLabels"]["node"]);
+    assert_eq!(
+        "stackable-linux",
+        role_groups["b"]["selector"]["matchLabels"]["kubernetes.io/arch"]
+    );
+    assert_eq!(2182, role_groups["b"]["config"]["clientPort"]);
+    assert_eq!(9505, role_groups["b"]["config"]["metricsPort"]);
+    assert_eq!(
+        "3000",
+        role_groups["a"]["configOverrides"]["zoo.cfg"]["tickTime"]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_zk_cluster_builder_requires_role_group() {
+    assert!(ZkClusterBuilder::new("simple", &ZookeeperVersion::v3_4_14)
+        .build()
+        .is_err());
+}
+
+#[test]
+fn test_version_transitions() -> Result<()> {
+    let versions = all_versions();
+    for pair in versions.windows(2) {
+        assert_eq!(
+            VersionTransition::Upgrade,
+            version_transition(&pair[0], &pair[1])?
+        );
+        assert_eq!(
+            VersionTransition::Downgrade,
+            version_transition(&pair[1], &pair[0])?
+        );
+    }
+    for version in &versions {
+        assert!(version_transition(version, version).is_err());
+    }
+    Ok(())
+}